@@ -0,0 +1,257 @@
+//! Compares deserializing a DAT directly from its input with `de::from_str` against parsing it
+//! into a `DatDocument` first and deserializing from the document, for a DAT of consecutive
+//! games and for a DAT like MAME's, whose fragments and ROMs are interleaved with other keys.
+//!
+//! Run with `cargo bench --features test_deserialize`. The number of games can be set with the
+//! `LISTINFO_BENCH_GAMES` environment variable.
+#![allow(dead_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use listinfo::de;
+use listinfo::hash::Crc32;
+use listinfo::parse::parse_document;
+use serde::Deserialize;
+
+/// An allocator that counts allocations and the peak number of bytes allocated.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        if new_size > layout.size() {
+            let grown = new_size - layout.size();
+            let allocated = ALLOCATED.fetch_add(grown, Ordering::Relaxed) + grown;
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        } else {
+            ALLOCATED.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Deserialize)]
+struct Rom<'a> {
+    name: &'a str,
+    size: u64,
+    crc: Crc32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Game<'a> {
+    name: &'a str,
+    description: &'a str,
+    year: Option<u32>,
+    cloneof: Option<&'a str>,
+    #[serde(borrow)]
+    rom: Vec<Rom<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dat<'a> {
+    #[serde(borrow)]
+    game: Vec<Game<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Disk<'a> {
+    name: &'a str,
+    sha1: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct Machine<'a> {
+    name: &'a str,
+    description: &'a str,
+    #[serde(borrow)]
+    rom: Vec<Rom<'a>>,
+    #[serde(borrow, default)]
+    disk: Vec<Disk<'a>>,
+    #[serde(borrow, default)]
+    device_ref: Vec<&'a str>,
+    /// Deserializes every other key as it is, as self-describing types do.
+    #[serde(flatten)]
+    other: HashMap<String, serde::de::IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MachineDat<'a> {
+    #[serde(borrow)]
+    game: Vec<Machine<'a>>,
+    #[serde(borrow)]
+    resource: Vec<Machine<'a>>,
+}
+
+/// Generates a DAT with the given number of games, with four ROMs each.
+fn generate(games: usize) -> String {
+    let mut dat = String::from(
+        "clrmamepro (\n\tname \"Benchmark\"\n\tdescription \"Benchmark\"\n\tversion 1\n)\n\n",
+    );
+    for game in 0..games {
+        let _ = write!(
+            dat,
+            "game (\n\tname \"game{0:06}\"\n\tdescription \"Game {0} (World)\"\n\tyear {1}\n\tmanufacturer \"Manufacturer\"\n",
+            game,
+            1980 + game % 40
+        );
+        if game % 3 != 0 {
+            let _ = writeln!(dat, "\tcloneof \"game{:06}\"", game - game % 3);
+        }
+        for rom in 0..4 {
+            let _ = writeln!(
+                dat,
+                "\trom ( name game{:06}.{} size {} crc {:08x} )",
+                game,
+                rom,
+                4096 << rom,
+                game * 4 + rom
+            );
+        }
+        dat.push_str(")\n\n");
+    }
+    dat
+}
+
+/// Generates a DAT like MAME's with the given number of games, with a device `resource` after
+/// every game, and the disks, device references and other values of each game between its ROMs.
+fn generate_interleaved(games: usize) -> String {
+    let mut dat = String::from("clrmamepro (\n\tname \"Benchmark\"\n)\n\n");
+    for game in 0..games {
+        let _ = writeln!(
+            dat,
+            "game (\n\tname \"game{0:06}\"\n\tdescription \"Game {0}\"",
+            game
+        );
+        for rom in 0..8 {
+            let _ = writeln!(
+                dat,
+                "\trom ( name game{:06}.{} size {} crc {:08x} )",
+                game,
+                rom,
+                4096 << rom,
+                game * 8 + rom
+            );
+            let _ = match rom % 4 {
+                0 => writeln!(dat, "\tdisk ( name disk{} sha1 {:040x} )", rom, game),
+                1 => writeln!(dat, "\tdevice_ref device{:06}", game),
+                2 => writeln!(dat, "\tchip ( type cpu name Z80 clock 3072000 )"),
+                _ => writeln!(dat, "\tsample sample{}", rom),
+            };
+        }
+        dat.push_str(")\n\n");
+        let _ = writeln!(
+            dat,
+            "resource (\n\tname \"device{0:06}\"\n\tdescription \"Device {0}\"\n\trom ( name device{0:06}.bin size 1024 crc {0:08x} )\n)\n",
+            game
+        );
+    }
+    dat
+}
+
+struct Measurement {
+    time: Duration,
+    allocations: usize,
+    peak: usize,
+}
+
+/// Runs the function a few times, keeping the fastest time and the allocations of the last run.
+fn measure(mut f: impl FnMut() -> usize) -> Measurement {
+    let mut time = Duration::MAX;
+    let mut allocations = 0;
+    let mut peak = 0;
+    for _ in 0..5 {
+        let baseline = ALLOCATED.load(Ordering::Relaxed);
+        PEAK.store(baseline, Ordering::Relaxed);
+        let start_allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let games = f();
+        time = time.min(start.elapsed());
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - start_allocations;
+        peak = PEAK.load(Ordering::Relaxed) - baseline;
+        assert!(games > 0);
+    }
+    Measurement {
+        time,
+        allocations,
+        peak,
+    }
+}
+
+fn report(name: &str, m: &Measurement) {
+    println!(
+        "{:<12} {:>10.2?} {:>12} allocations {:>10.1} MiB peak",
+        name,
+        m.time,
+        m.allocations,
+        m.peak as f64 / (1024.0 * 1024.0)
+    );
+}
+
+fn main() {
+    let games = std::env::var("LISTINFO_BENCH_GAMES")
+        .ok()
+        .and_then(|games| games.parse().ok())
+        .unwrap_or(50_000);
+    let dat = generate(games);
+    println!(
+        "{} games, {:.1} MiB",
+        games,
+        dat.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    let two_phase = measure(|| {
+        let document = parse_document(&dat).unwrap();
+        let parsed: Dat<'_> = de::from_document(&document).unwrap();
+        parsed.game.len()
+    });
+    let stream = measure(|| {
+        let parsed: Dat<'_> = de::from_str(&dat).unwrap();
+        parsed.game.len()
+    });
+
+    report("two-phase", &two_phase);
+    report("streaming", &stream);
+
+    let dat = generate_interleaved(games);
+    println!(
+        "{} interleaved games and devices, {:.1} MiB",
+        games,
+        dat.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    let two_phase = measure(|| {
+        let document = parse_document(&dat).unwrap();
+        let parsed: MachineDat<'_> = de::from_document(&document).unwrap();
+        parsed.game.len()
+    });
+    let stream = measure(|| {
+        let parsed: MachineDat<'_> = de::from_str(&dat).unwrap();
+        parsed.game.len()
+    });
+
+    report("two-phase", &two_phase);
+    report("streaming", &stream);
+}
@@ -0,0 +1,613 @@
+//! A lossless syntax tree of ListInfo DATs, for editing DATs in place.
+//!
+//! [`parse_document`](fn.parse_document.html) keeps every key and value of a DAT as it was
+//! written, along with the whitespace around it, so that writing the tree back out with
+//! `Display` reproduces the input byte for byte. Entries can be changed, added and removed
+//! through the tree, and only the text of the entries that were edited changes, so DATs kept
+//! under version control get minimal diffs. ListInfo has no comment syntax of its own;
+//! `comment` values are ordinary entries and are kept in place like any other.
+//!
+//! Edited values keep their quotes, and are quoted when needed. New entries copy the leading
+//! whitespace of the last entry of their fragment or sub-entry, so they follow the layout
+//! around them.
+//!
+//! ```rust
+//! use listinfo::cst;
+//!
+//! const DAT: &str = "game (\n  name   pacman\n  rom ( name pacman.6e size 4096 crc c1e6ab10 )\n)\n";
+//!
+//! let mut document = cst::parse_document(DAT).unwrap();
+//! assert_eq!(document.to_string(), DAT);
+//!
+//! let game = document.find_mut("game", "pacman").unwrap();
+//! game.set("year", "1980");
+//! let rom = game.entry_mut("rom").and_then(|rom| rom.as_sub_entry_mut()).unwrap();
+//! rom.set("crc", "c1e6ab11");
+//! assert_eq!(
+//!     document.to_string(),
+//!     "game (\n  name   pacman\n  rom ( name pacman.6e size 4096 crc c1e6ab11 )\n  year 1980\n)\n"
+//! );
+//! ```
+//!
+//! The tree can be read with the element API through [`Document::to_document`](struct.Document.html#method.to_document).
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
+use core::slice;
+
+use nom::{
+    branch::alt,
+    character::complete::{char, multispace0, space0},
+    multi::{many0, many1},
+    Err,
+};
+
+use crate::elements::{self, DatDocument, EntryData, EntryFragment, Node};
+use crate::error::Error;
+use crate::parse::{self, DatError, IResult};
+use crate::write::{needs_quotes, writes_nothing, Escaped};
+
+/// The leading whitespace of the first entry added to an empty fragment,
+/// and the whitespace before its closing parenthesis.
+const FRAGMENT_LAYOUT: (&str, &str) = ("\n\t", "\n");
+
+/// The leading whitespace of the first entry added to an empty sub-entry,
+/// and the whitespace before its closing parenthesis.
+const SUB_ENTRY_LAYOUT: (&str, &str) = (" ", " ");
+
+/// The whitespace before the first fragment added after an existing one.
+const FRAGMENT_SEPARATOR: &str = "\n\n";
+
+/// A ListInfo DAT with the layout of every fragment and entry.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Document<'a> {
+    bom: bool,
+    fragments: Vec<Fragment<'a>>,
+    trailing: Cow<'a, str>,
+}
+
+/// A top level fragment of a DAT, such as `game ( ... )`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Fragment<'a> {
+    leading: Cow<'a, str>,
+    key: Cow<'a, str>,
+    body: Body<'a>,
+}
+
+/// The entries of a sub-entry, such as `rom ( ... )`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SubEntry<'a> {
+    body: Body<'a>,
+}
+
+/// The parenthesized entries of a fragment or sub-entry.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Body<'a> {
+    /// The whitespace between the key and the opening parenthesis.
+    open: Cow<'a, str>,
+    entries: Vec<Entry<'a>>,
+    /// The whitespace before the closing parenthesis.
+    close: Cow<'a, str>,
+}
+
+/// A key and its value or sub-entry, within a fragment or sub-entry.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Entry<'a> {
+    leading: Cow<'a, str>,
+    key: Cow<'a, str>,
+    value: Value<'a>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Value<'a> {
+    Scalar {
+        separator: Cow<'a, str>,
+        /// The value as it was written, including quotes and escapes.
+        raw: Cow<'a, str>,
+        value: Cow<'a, str>,
+    },
+    SubEntry(SubEntry<'a>),
+}
+
+/// Parses a ListInfo DAT into a lossless syntax tree.
+///
+/// The tree is parsed with the same grammar as [`parse::parse_document`](../parse/fn.parse_document.html),
+/// which reports the same errors.
+pub fn parse_document(input: &str) -> Result<Document<'_>, Error> {
+    let body = parse::skip_bom(input);
+    let mut document = Document {
+        bom: body.len() != input.len(),
+        fragments: Vec::new(),
+        trailing: Cow::Borrowed(""),
+    };
+    let mut rest = body;
+    loop {
+        // A document must contain at least one fragment.
+        if rest.trim_start_matches(parse::is_space).is_empty() && !document.fragments.is_empty() {
+            document.trailing = Cow::Borrowed(rest);
+            return Ok(document);
+        }
+        match fragment(rest) {
+            Ok((remaining, fragment)) => {
+                document.fragments.push(fragment);
+                rest = remaining;
+            }
+            Err(err) => {
+                return Err(parse::to_error(
+                    err,
+                    input,
+                    rest.trim_start_matches(parse::is_space),
+                ))
+            }
+        }
+    }
+}
+
+fn fragment(input: &str) -> IResult<'_, Fragment<'_>> {
+    let (input, leading) = multispace0(input)?;
+    let (input, key) = parse::string_key(input)?;
+    let (input, open) = multispace0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, entries) = many0(|input| entry(input, 1))(input)?;
+    let (rest, close) = multispace0(input)?;
+    let (rest, _) = match char(')')(rest) {
+        Ok(closed) => closed,
+        // The fragment may not be closed because an entry failed to parse,
+        // in which case the entry error is reported as it is by the element parser.
+        Err(Err::Error(close_err)) => {
+            return Err(Err::Error(match entry(input, 1) {
+                Err(Err::Error(entry_err)) => nom::error::ParseError::or(close_err, entry_err),
+                _ => close_err,
+            }))
+        }
+        Err(err) => return Err(err),
+    };
+    let body = Body {
+        open: Cow::Borrowed(open),
+        entries,
+        close: Cow::Borrowed(close),
+    };
+    Ok((
+        rest,
+        Fragment {
+            leading: Cow::Borrowed(leading),
+            key: Cow::Borrowed(key),
+            body,
+        },
+    ))
+}
+
+/// Parses a value or a sub-entry nested `depth` levels deep.
+fn entry(input: &str, depth: usize) -> IResult<'_, Entry<'_>> {
+    let (input, leading) = multispace0(input)?;
+    let (input, key) = parse::string_key(input)?;
+    let (input, value) = alt((|input| sub_entry(input, depth), scalar))(input)?;
+    Ok((
+        input,
+        Entry {
+            leading: Cow::Borrowed(leading),
+            key: Cow::Borrowed(key),
+            value,
+        },
+    ))
+}
+
+fn sub_entry(input: &str, depth: usize) -> IResult<'_, Value<'_>> {
+    let (input, open) = space0(input)?;
+    let (rest, _) = char('(')(input)?;
+    if depth > parse::MAX_DEPTH {
+        return Err(Err::Failure(DatError::too_deep(input)));
+    }
+    let input = rest;
+    let (input, entries) = many1(|input| entry(input, depth + 1))(input)?;
+    let (input, close) = multispace0(input)?;
+    let (input, _) = char(')')(input)?;
+    let body = Body {
+        open: Cow::Borrowed(open),
+        entries,
+        close: Cow::Borrowed(close),
+    };
+    Ok((input, Value::SubEntry(SubEntry { body })))
+}
+
+fn scalar(input: &str) -> IResult<'_, Value<'_>> {
+    let (input, separator) = parse::separator(input)?;
+    let (rest, value) = alt((parse::quoted_string, parse::unquoted_string))(input)?;
+    let raw = &input[..input.len() - rest.len()];
+    Ok((
+        rest,
+        Value::Scalar {
+            separator: Cow::Borrowed(separator),
+            raw: Cow::Borrowed(raw),
+            value: parse::trim(value),
+        },
+    ))
+}
+
+/// Writes a value as it would be written by the element API, quoting it if it is quoted
+/// already or must be quoted to be read back.
+fn raw_value(value: &str, quoted: bool) -> String {
+    if quoted || needs_quotes(value) {
+        format!("\"{}\"", Escaped(value))
+    } else {
+        String::from(value)
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Creates a value laid out as the element API writes it, keeping the quotes of the
+    /// value it replaces.
+    fn new(data: EntryData<'a>, quoted: bool) -> Self {
+        match data {
+            EntryData::Scalar(value) => Value::Scalar {
+                separator: Cow::Borrowed(" "),
+                raw: Cow::Owned(raw_value(&value, quoted)),
+                value,
+            },
+            EntryData::SubEntry(sub_entry) => Value::SubEntry(SubEntry::from(sub_entry)),
+        }
+    }
+
+    /// Whether the value is a sub-entry that contains no values, even through nested
+    /// sub-entries, which is not written.
+    fn is_blank(&self) -> bool {
+        match self {
+            Value::Scalar { .. } => false,
+            Value::SubEntry(sub_entry) => sub_entry.body.entries.iter().all(|e| e.value.is_blank()),
+        }
+    }
+}
+
+impl<'a> From<elements::SubEntry<'a>> for SubEntry<'a> {
+    /// Lays out a sub-entry of the element API on a single line, like `rom ( name x size 1 )`.
+    ///
+    /// Sub-entries that contain no values are left out, since they could not be parsed.
+    fn from(sub_entry: elements::SubEntry<'a>) -> Self {
+        let mut body = Body {
+            open: Cow::Borrowed(" "),
+            entries: Vec::new(),
+            close: Cow::Borrowed(" "),
+        };
+        for (key, node) in sub_entry.keys {
+            for data in node
+                .into_vec()
+                .into_iter()
+                .filter(|data| !writes_nothing(data))
+            {
+                body.entries.push(Entry {
+                    leading: Cow::Borrowed(" "),
+                    key: key.clone(),
+                    value: Value::new(data, false),
+                });
+            }
+        }
+        SubEntry { body }
+    }
+}
+
+impl<'a> Body<'a> {
+    fn new() -> Self {
+        Body {
+            open: Cow::Borrowed(" "),
+            entries: Vec::new(),
+            close: Cow::Borrowed(""),
+        }
+    }
+
+    /// Appends an entry with the leading whitespace of the last entry, or with the given
+    /// layout if there are no entries yet.
+    fn push(
+        &mut self,
+        key: Cow<'a, str>,
+        data: EntryData<'a>,
+        (indent, close): (&'static str, &'static str),
+    ) {
+        let leading = match self.entries.last() {
+            Some(last) => last.leading.clone(),
+            None => {
+                if self.close.is_empty() {
+                    self.close = Cow::Borrowed(close);
+                }
+                Cow::Borrowed(indent)
+            }
+        };
+        self.entries.push(Entry {
+            leading,
+            key,
+            value: Value::new(data, false),
+        });
+    }
+
+    fn to_keys(&self) -> indexmap::IndexMap<Cow<'_, str>, Node<EntryData<'_>>> {
+        let mut keys = elements::SubEntry::new();
+        for entry in self.entries.iter().filter(|entry| !entry.value.is_blank()) {
+            let data = match &entry.value {
+                Value::Scalar { value, .. } => EntryData::Scalar(Cow::Borrowed(&**value)),
+                Value::SubEntry(sub_entry) => EntryData::SubEntry(elements::SubEntry {
+                    keys: sub_entry.body.to_keys(),
+                }),
+            };
+            keys.push(&*entry.key, data);
+        }
+        keys.keys
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_str(&self.open)?;
+        w.write_char('(')?;
+        for entry in self.entries.iter().filter(|entry| !entry.value.is_blank()) {
+            entry.write_to(w)?;
+        }
+        w.write_str(&self.close)?;
+        w.write_char(')')
+    }
+}
+
+/// Implements the methods shared by `Fragment` and `SubEntry`.
+macro_rules! impl_entries {
+    ($ty:ident, $layout:expr) => {
+        impl<'a> $ty<'a> {
+            /// Gets the entries in the order they are written.
+            pub fn entries(&self) -> slice::Iter<'_, Entry<'a>> {
+                self.body.entries.iter()
+            }
+
+            /// Gets the entries mutably, in the order they are written.
+            pub fn entries_mut(&mut self) -> slice::IterMut<'_, Entry<'a>> {
+                self.body.entries.iter_mut()
+            }
+
+            /// Gets the first entry with the given key if it exists.
+            pub fn entry(&self, key: &str) -> Option<&Entry<'a>> {
+                self.body.entries.iter().find(|entry| entry.key == key)
+            }
+
+            /// Gets the first entry with the given key mutably if it exists.
+            pub fn entry_mut(&mut self, key: &str) -> Option<&mut Entry<'a>> {
+                self.body.entries.iter_mut().find(|entry| entry.key == key)
+            }
+
+            /// Gets the first value with the given key, unless it is a sub-entry.
+            pub fn value(&self, key: &str) -> Option<&str> {
+                self.entry(key).and_then(Entry::as_scalar)
+            }
+
+            /// Appends a value with the given key, returning `self` for building entries in order.
+            pub fn with(
+                mut self,
+                key: impl Into<Cow<'a, str>>,
+                value: impl Into<EntryData<'a>>,
+            ) -> Self {
+                self.push(key, value);
+                self
+            }
+
+            /// Appends a value with the given key after the last entry.
+            pub fn push(&mut self, key: impl Into<Cow<'a, str>>, value: impl Into<EntryData<'a>>) {
+                self.body.push(key.into(), value.into(), $layout);
+            }
+
+            /// Replaces the first value with the given key, or appends it if there is none.
+            pub fn set(&mut self, key: impl Into<Cow<'a, str>>, value: impl Into<EntryData<'a>>) {
+                let key = key.into();
+                match self.entry_mut(&key) {
+                    Some(entry) => entry.set_value(value),
+                    None => self.body.push(key, value.into(), $layout),
+                }
+            }
+
+            /// Removes all entries with the given key, returning them in order.
+            pub fn remove(&mut self, key: &str) -> Vec<Entry<'a>> {
+                let (removed, kept) = core::mem::take(&mut self.body.entries)
+                    .into_iter()
+                    .partition(|entry| entry.key == key);
+                self.body.entries = kept;
+                removed
+            }
+
+            /// Keeps only the entries for which the predicate returns `true`.
+            pub fn retain<F: FnMut(&Entry<'a>) -> bool>(&mut self, f: F) {
+                self.body.entries.retain(f);
+            }
+        }
+    };
+}
+
+impl_entries!(Fragment, FRAGMENT_LAYOUT);
+impl_entries!(SubEntry, SUB_ENTRY_LAYOUT);
+
+impl<'a> Document<'a> {
+    /// Gets the fragments in the order they are written.
+    pub fn fragments(&self) -> slice::Iter<'_, Fragment<'a>> {
+        self.fragments.iter()
+    }
+
+    /// Gets the fragments mutably, in the order they are written.
+    pub fn fragments_mut(&mut self) -> slice::IterMut<'_, Fragment<'a>> {
+        self.fragments.iter_mut()
+    }
+
+    /// Gets the first fragment with the given key and `name`.
+    pub fn find(&self, key: &str, name: &str) -> Option<&Fragment<'a>> {
+        self.fragments
+            .iter()
+            .find(|fragment| fragment.key == key && fragment.name() == Some(name))
+    }
+
+    /// Gets the first fragment with the given key and `name` mutably.
+    pub fn find_mut(&mut self, key: &str, name: &str) -> Option<&mut Fragment<'a>> {
+        self.fragments
+            .iter_mut()
+            .find(|fragment| fragment.key == key && fragment.name() == Some(name))
+    }
+
+    /// Appends a fragment after the last fragment, separated from it like the fragments
+    /// before it, or by a blank line.
+    pub fn push(&mut self, mut fragment: Fragment<'a>) {
+        fragment.leading = match self.fragments.last() {
+            Some(last) if !last.leading.is_empty() => last.leading.clone(),
+            Some(_) => Cow::Borrowed(FRAGMENT_SEPARATOR),
+            None => Cow::Borrowed(""),
+        };
+        self.fragments.push(fragment);
+    }
+
+    /// Removes the fragment at the given index, if it exists.
+    ///
+    /// The whitespace before the first fragment is kept before the new first fragment.
+    pub fn remove(&mut self, index: usize) -> Option<Fragment<'a>> {
+        if index >= self.fragments.len() {
+            return None;
+        }
+        let removed = self.fragments.remove(index);
+        if let (0, Some(first)) = (index, self.fragments.first_mut()) {
+            first.leading = removed.leading.clone();
+        }
+        Some(removed)
+    }
+
+    /// Keeps only the fragments for which the predicate returns `true`.
+    ///
+    /// The whitespace before the first fragment is kept before the new first fragment.
+    pub fn retain<F: FnMut(&Fragment<'a>) -> bool>(&mut self, f: F) {
+        let leading = self.fragments.first().map(|first| first.leading.clone());
+        self.fragments.retain(f);
+        if let (Some(leading), Some(first)) = (leading, self.fragments.first_mut()) {
+            first.leading = leading;
+        }
+    }
+
+    /// Converts the tree into a document of the element API, borrowing its keys and values.
+    pub fn to_document(&self) -> DatDocument<'_> {
+        let mut document = DatDocument::new();
+        for fragment in &self.fragments {
+            document.push(
+                &*fragment.key,
+                EntryFragment {
+                    keys: fragment.body.to_keys(),
+                },
+            );
+        }
+        document
+    }
+}
+
+impl<'a> Display for Document<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            f.write_char('\u{feff}')?;
+        }
+        for fragment in &self.fragments {
+            f.write_str(&fragment.leading)?;
+            f.write_str(&fragment.key)?;
+            fragment.body.write_to(f)?;
+        }
+        f.write_str(&self.trailing)
+    }
+}
+
+impl<'a> Fragment<'a> {
+    /// Creates a fragment with the given key and no entries.
+    pub fn new(key: impl Into<Cow<'a, str>>) -> Self {
+        Fragment {
+            leading: Cow::Borrowed(""),
+            key: key.into(),
+            body: Body::new(),
+        }
+    }
+
+    /// Gets the key of the fragment, such as `game`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Gets the `name` value of the fragment.
+    pub fn name(&self) -> Option<&str> {
+        self.value("name")
+    }
+}
+
+impl<'a> SubEntry<'a> {
+    /// Creates a sub-entry without any entries.
+    ///
+    /// A sub-entry must have at least one entry to be read back.
+    pub fn new() -> Self {
+        SubEntry { body: Body::new() }
+    }
+}
+
+impl<'a> Default for SubEntry<'a> {
+    fn default() -> Self {
+        SubEntry::new()
+    }
+}
+
+impl<'a> Entry<'a> {
+    /// Gets the key of the entry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Renames the entry, keeping its value.
+    pub fn rename(&mut self, key: impl Into<Cow<'a, str>>) {
+        self.key = key.into();
+    }
+
+    /// Gets the value of the entry, unless it is a sub-entry.
+    ///
+    /// Quoted values are returned without their quotes and escapes.
+    pub fn as_scalar(&self) -> Option<&str> {
+        match &self.value {
+            Value::Scalar { value, .. } => Some(value),
+            Value::SubEntry(_) => None,
+        }
+    }
+
+    /// Gets the sub-entry of the entry, if it is one.
+    pub fn as_sub_entry(&self) -> Option<&SubEntry<'a>> {
+        match &self.value {
+            Value::SubEntry(sub_entry) => Some(sub_entry),
+            Value::Scalar { .. } => None,
+        }
+    }
+
+    /// Gets the sub-entry of the entry mutably, if it is one.
+    pub fn as_sub_entry_mut(&mut self) -> Option<&mut SubEntry<'a>> {
+        match &mut self.value {
+            Value::SubEntry(sub_entry) => Some(sub_entry),
+            Value::Scalar { .. } => None,
+        }
+    }
+
+    /// Replaces the value of the entry.
+    ///
+    /// A value that replaces a quoted value is quoted as well, and a value that replaces
+    /// another value keeps the whitespace after the key.
+    pub fn set_value(&mut self, value: impl Into<EntryData<'a>>) {
+        let (separator, quoted) = match &self.value {
+            Value::Scalar { separator, raw, .. } => (Some(separator.clone()), raw.starts_with('"')),
+            Value::SubEntry(_) => (None, false),
+        };
+        self.value = Value::new(value.into(), quoted);
+        if let (Value::Scalar { separator: new, .. }, Some(separator)) =
+            (&mut self.value, separator)
+        {
+            *new = separator;
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_str(&self.leading)?;
+        w.write_str(&self.key)?;
+        match &self.value {
+            Value::Scalar { separator, raw, .. } => {
+                w.write_str(separator)?;
+                w.write_str(raw)
+            }
+            Value::SubEntry(sub_entry) => sub_entry.body.write_to(w),
+        }
+    }
+}
@@ -4,7 +4,10 @@ use crate::Error;
 
 use alloc::string::ToString;
 use core::result::Result as CoreResult;
-use serde::de::{DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
 use serde::forward_to_deserialize_any;
 
 type Result<T> = CoreResult<T, Error>;
@@ -12,7 +15,7 @@ type Result<T> = CoreResult<T, Error>;
 /// A deserializer for a ListInfo DAT.
 pub struct DatDocumentDeserializer<'de> {
     iter: SliceIter<'de, EntryFragment<'de>>,
-    value: Option<&'de [EntryFragment<'de>]>,
+    value: Option<(&'de str, &'de [EntryFragment<'de>])>,
 }
 
 impl<'de> DatDocumentDeserializer<'de> {
@@ -35,8 +38,8 @@ impl<'de> MapAccess<'de> for DatDocumentDeserializer<'de> {
     {
         match self.iter.next() {
             Some((key, value)) => {
-                self.value = Some(value);
-                let key_de = key.into_deserializer();
+                self.value = Some((key, value));
+                let key_de = BorrowedStrDeserializer::<Error>::new(key);
                 seed.deserialize(key_de).map(Some)
             }
             None => Ok(None),
@@ -48,7 +51,9 @@ impl<'de> MapAccess<'de> for DatDocumentDeserializer<'de> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(FragmentSliceDeserializer::new(value)),
+            Some((key, value)) => seed
+                .deserialize(FragmentSliceDeserializer::new(value))
+                .map_err(|e| e.at_key(key)),
             None => Err(crate::Error::SerdeError("value is missing".to_string())),
         }
     }
@@ -64,18 +69,25 @@ impl<'de> MapAccess<'de> for DatDocumentDeserializer<'de> {
 type FragmentSliceIter<'a> = core::slice::Iter<'a, EntryFragment<'a>>;
 struct FragmentSliceDeserializer<'a> {
     iter: FragmentSliceIter<'a>,
-    item: &'a EntryFragment<'a>,
+    item: Option<&'a EntryFragment<'a>>,
+    index: usize,
 }
 
 impl<'a> FragmentSliceDeserializer<'a> {
     pub(crate) fn new(n: &'a [EntryFragment<'a>]) -> Self {
         FragmentSliceDeserializer {
             iter: n.iter(),
-            item: n.first().unwrap(),
+            item: n.first(),
+            index: 0,
         }
     }
 }
 
+/// Gets the `name` of a fragment, which is kept with the paths of errors within it.
+fn name<'a>(fragment: &'a EntryFragment<'a>) -> Option<&'a str> {
+    fragment.entry_unique("name").and_then(EntryData::as_scalar)
+}
+
 impl<'de> SeqAccess<'de> for FragmentSliceDeserializer<'de> {
     type Error = crate::Error;
 
@@ -84,7 +96,13 @@ impl<'de> SeqAccess<'de> for FragmentSliceDeserializer<'de> {
         T: DeserializeSeed<'de>,
     {
         match self.iter.next() {
-            Some(fragment) => seed.deserialize(fragment.into_deserializer()).map(Some),
+            Some(fragment) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(fragment.into_deserializer())
+                    .map(Some)
+                    .map_err(|e| e.in_fragment(name(fragment)).at_index(index))
+            }
             None => Ok(None),
         }
     }
@@ -118,7 +136,12 @@ impl<'de> Deserializer<'de> for FragmentSliceDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_map(self.item.into_deserializer())
+        match self.item {
+            Some(item) => visitor
+                .visit_map(item.into_deserializer())
+                .map_err(|e| e.in_fragment(name(item))),
+            None => Err(de::Error::invalid_length(0, &"a fragment")),
+        }
     }
 
     fn deserialize_struct<V>(
@@ -137,7 +160,10 @@ impl<'de> Deserializer<'de> for FragmentSliceDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_some(self)
+        match self.item {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
@@ -0,0 +1,69 @@
+use crate::elements::*;
+use crate::parse::parse_document;
+use crate::Error;
+
+use core::result::Result as CoreResult;
+use serde::de::Deserialize;
+
+type Result<T> = CoreResult<T, Error>;
+
+/// A parsed ListInfo DAT that types borrowing from it can be deserialized from.
+///
+/// `from_str` deserializes values as it parses them, so values with escape sequences, which are
+/// copied while parsing, can not be borrowed into `&str`. A `Document` keeps the parsed document,
+/// so that types with `&str` and `#[serde(borrow)] Cow<str>` fields can borrow every value from
+/// it. Values that do not contain escape sequences point directly into the input, and are never
+/// copied.
+///
+/// ```rust
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Game<'a> {
+///     name: &'a str,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Dat<'a> {
+///     #[serde(borrow)]
+///     game: Vec<Game<'a>>,
+/// }
+///
+/// const DAT: &str = r#"game ( name "Cave Story (En)" )"#;
+///
+/// let document = listinfo::de::Document::parse(DAT).unwrap();
+/// let dat = document.deserialize::<Dat<'_>>().unwrap();
+/// assert_eq!(dat.game[0].name, "Cave Story (En)");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    document: DatDocument<'a>,
+}
+
+impl<'a> Document<'a> {
+    /// Parses the string contents of a ListInfo DAT.
+    pub fn parse(input: &'a str) -> Result<Self> {
+        parse_document(input).map(Document::from)
+    }
+
+    /// Deserializes a type that may borrow from the document.
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T> {
+        super::from_document(&self.document)
+    }
+
+    /// Gets the parsed document.
+    pub fn as_dat_document(&self) -> &DatDocument<'a> {
+        &self.document
+    }
+
+    /// Takes the parsed document.
+    pub fn into_dat_document(self) -> DatDocument<'a> {
+        self.document
+    }
+}
+
+impl<'a> From<DatDocument<'a>> for Document<'a> {
+    fn from(document: DatDocument<'a>) -> Self {
+        Document { document }
+    }
+}
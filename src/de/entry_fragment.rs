@@ -2,8 +2,10 @@ use crate::elements::*;
 use crate::iter::*;
 use crate::Error;
 
+use alloc::borrow::Cow;
 use alloc::string::ToString;
 use core::result::Result as CoreResult;
+use serde::de::value::BorrowedStrDeserializer;
 use serde::de::{DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor};
 use serde::forward_to_deserialize_any;
 
@@ -11,12 +13,12 @@ type Result<T> = CoreResult<T, Error>;
 
 /// A deserializer for a ListInfo DAT fragment.
 pub struct EntryFragmentDeserializer<'de> {
-    iter: EntryIter<'de, &'de str, Node<EntryData<'de>>>,
-    value: Option<&'de Node<EntryData<'de>>>,
+    iter: EntryIter<'de, Cow<'de, str>, Node<EntryData<'de>>>,
+    value: Option<(&'de str, &'de Node<EntryData<'de>>)>,
 }
 
 impl<'de> EntryFragmentDeserializer<'de> {
-    pub(crate) fn new(iter: EntryIter<'de, &'de str, Node<EntryData<'de>>>) -> Self {
+    pub(crate) fn new(iter: EntryIter<'de, Cow<'de, str>, Node<EntryData<'de>>>) -> Self {
         EntryFragmentDeserializer { iter, value: None }
     }
 
@@ -35,8 +37,8 @@ impl<'de> MapAccess<'de> for EntryFragmentDeserializer<'de> {
     {
         match self.iter.next() {
             Some((key, value)) => {
-                self.value = Some(value);
-                let key_de = key.into_deserializer();
+                self.value = Some((key, value));
+                let key_de = BorrowedStrDeserializer::<Error>::new(key);
                 seed.deserialize(key_de).map(Some)
             }
             None => Ok(None),
@@ -48,7 +50,9 @@ impl<'de> MapAccess<'de> for EntryFragmentDeserializer<'de> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(value.into_deserializer()),
+            Some((key, value)) => seed
+                .deserialize(value.into_deserializer())
+                .map_err(|e| e.at_key(key)),
             None => Err(crate::Error::SerdeError("value is missing".to_string())),
         }
     }
@@ -4,7 +4,7 @@
 //! This must first be enabled in Cargo.toml
 //!
 //! ```toml
-//! listinfo = { version = "0.3", features = ["deserialize"] }
+//! listinfo = { version = "0.4", features = ["deserialize"] }
 //! ```
 //!
 //! ## Usage
@@ -78,10 +78,62 @@
 //!     assert_eq!(cave_story.game.first().unwrap().rom.first().unwrap().crc, &[0xc5, 0xa2, 0xa3, 0xf6]);
 //! }
 //! ```
+//!
+//! ## Self-describing targets
+//! DATs can also be deserialized into self-describing types such as `serde_json::Value`,
+//! `#[serde(untagged)]` enums and `#[serde(flatten)]` fields. Values are deserialized as strings,
+//! keys that are repeated as sequences, and sub-entries as maps, while the fragments of a document
+//! are always deserialized as sequences. Since values are strings, fields of types that serde
+//! buffers, such as flattened or untagged types, must be strings as well.
+//!
+//! ```rust
+//! let value = listinfo::de::from_str::<serde_json::Value>("game ( name pacman rom ( name pacman.6e ) )").unwrap();
+//! assert_eq!(value["game"][0]["rom"]["name"], "pacman.6e");
+//! ```
+//!
+//! ## Borrowing
+//! `from_str` deserializes a DAT as it parses it, without building a `DatDocument`, and can
+//! deserialize types with `&str` and `#[serde(borrow)] Cow<str>` fields that borrow from the input.
+//! Values with escape sequences are copied while parsing, and can only be borrowed into `&str`
+//! from a parsed [`Document`](struct.Document.html), which keeps the copies.
+//!
+//! ## Errors
+//! Errors about a value carry the path to the value, along with the `name` of the fragment it
+//! is in, which can be retrieved with [`Error::path`](../enum.Error.html#method.path).
+//!
+//! ```rust
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Rom {
+//!     size: u64,
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Game {
+//!     rom: Vec<Rom>,
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Dat {
+//!     game: Vec<Game>,
+//! }
+//!
+//! const DAT: &str = r#"game ( name "Foo (USA)" rom ( size 1 ) rom ( size abc ) )"#;
+//! let err = listinfo::de::from_str::<Dat>(DAT).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     r#"game[0] (name "Foo (USA)") → rom[1].size: invalid type: string "abc", expected u64"#
+//! );
+//! assert_eq!(err.path().unwrap().fragment_name(), Some("Foo (USA)"));
+//! ```
+#[macro_use]
+mod value;
+
 mod dat_document;
+mod document;
 mod entry_data;
 mod entry_fragment;
 mod node;
+mod stream;
 mod sub_entry;
 
 #[cfg(feature = "test_deserialize")]
@@ -89,18 +141,22 @@ mod tests;
 
 use node::NodeDeserializer;
 use sub_entry::SubEntryDeserializer;
+use value::ValueDeserializer;
 
-use serde::de::{Deserialize, DeserializeOwned, IntoDeserializer};
+use serde::de::{Deserialize, IntoDeserializer};
 
 use crate::elements::*;
-use crate::parse::parse_document;
 use crate::Error;
 pub use dat_document::DatDocumentDeserializer as Deserializer;
+pub use document::Document;
 pub use entry_fragment::EntryFragmentDeserializer as FragmentDeserializer;
+pub use stream::StreamDeserializer;
 
 type Result<T> = core::result::Result<T, crate::Error>;
 
 /// Deserialize from a parsed `DatDocument`.
+///
+/// This accepts both borrowed documents and an `OwnedDatDocument`.
 pub fn from_document<'de, T: Deserialize<'de>>(doc: &'de DatDocument<'de>) -> Result<T> {
     T::deserialize(Deserializer::new(doc.iter()))
 }
@@ -111,9 +167,13 @@ pub fn from_fragment<'de, T: Deserialize<'de>>(entry: &'de EntryFragment<'de>) -
 }
 
 /// Deserialize from the string contents of a ListInfo DAT.
-pub fn from_str<'de, T: DeserializeOwned>(s: &str) -> Result<T> {
-    let parsed = parse_document(s)?;
-    from_document(&parsed)
+///
+/// The DAT is deserialized as it is parsed with a [`StreamDeserializer`](struct.StreamDeserializer.html),
+/// without building a `DatDocument`. Values without escape sequences can be borrowed from the input,
+/// while types that borrow values with escape sequences must be deserialized from a
+/// [`Document`](struct.Document.html) instead.
+pub fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T> {
+    T::deserialize(&mut StreamDeserializer::new(s))
 }
 
 impl<'de> IntoDeserializer<'de, Error> for &'de DatDocument<'de> {
@@ -130,13 +190,6 @@ impl<'de> IntoDeserializer<'de, Error> for &'de EntryFragment<'de> {
     }
 }
 
-impl<'de> IntoDeserializer<'de, Error> for &'de Node<&'de str> {
-    type Deserializer = NodeDeserializer<'de, &'de str>;
-    fn into_deserializer(self) -> Self::Deserializer {
-        NodeDeserializer::new(self)
-    }
-}
-
 impl<'de> IntoDeserializer<'de, Error> for &'de Node<EntryData<'de>> {
     type Deserializer = NodeDeserializer<'de, EntryData<'de>>;
     fn into_deserializer(self) -> Self::Deserializer {
@@ -1,10 +1,7 @@
 use super::NodeDeserializer;
 use crate::{de::*, EntryData, Error};
-use alloc::string::String;
 use core::result::Result as CoreResult;
-use hex;
 use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
-use serde::serde_if_integer128;
 
 type Result<T> = CoreResult<T, Error>;
 
@@ -16,7 +13,13 @@ impl<'de> SeqAccess<'de> for NodeDeserializer<'de, EntryData<'de>> {
         T: DeserializeSeed<'de>,
     {
         match self.iter.next() {
-            Some(value) => seed.deserialize(value).map(Some),
+            Some(value) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(value)
+                    .map(Some)
+                    .map_err(|e| e.at_index(index))
+            }
             None => Ok(None),
         }
     }
@@ -29,48 +32,16 @@ impl<'de> SeqAccess<'de> for NodeDeserializer<'de, EntryData<'de>> {
     }
 }
 
-macro_rules! deserialize_primitive {
-    ($t:ty: $deserialize:ident => $visit:ident) => {
-        fn $deserialize<V: de::Visitor<'de>>(
-            self,
-            visitor: V,
-        ) -> core::result::Result<V::Value, Error> {
-            match self.item {
-                EntryData::Scalar(item) => {
-                    let value = item.parse::<$t>().map_err::<Error, _>(|_| {
-                        de::Error::invalid_type(de::Unexpected::Str(item), &visitor)
-                    })?;
-                    visitor.$visit(value)
-                }
-                _ => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
-            }
-        }
-    };
+impl<'de> NodeDeserializer<'de, EntryData<'de>> {
+    /// Gets the deserializer of the first value.
+    fn scalar(&self, expected: &dyn de::Expected) -> Result<ValueDeserializer<'de>> {
+        self.item()?.scalar(expected)
+    }
 }
 
 impl<'de> Deserializer<'de> for NodeDeserializer<'de, EntryData<'de>> {
     type Error = crate::Error;
 
-    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.item {
-            EntryData::Scalar(item) => visitor.visit_borrowed_str(*item),
-            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
-        }
-    }
-
-    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.item {
-            EntryData::Scalar(item) => visitor.visit_string(String::from(*item)),
-            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
-        }
-    }
-
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
@@ -78,74 +49,25 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, EntryData<'de>> {
         visitor.visit_seq(self)
     }
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    /// Deserializes repeated keys as a sequence, and a single value as itself.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
-    }
-
-    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.item {
-            EntryData::Scalar(item) => match item {
-                &"yes" => visitor.visit_bool(true),
-                &"no" => visitor.visit_bool(false),
-                _ => visitor.visit_bool(item.parse::<bool>().map_err::<Error, _>(|_| {
-                    de::Error::invalid_type(de::Unexpected::Str(item), &"bool")
-                })?),
-            },
-            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
+        if self.many {
+            visitor.visit_seq(self)
+        } else {
+            self.item()?.deserialize_any(visitor)
         }
     }
 
-    deserialize_primitive!(i8: deserialize_i8 => visit_i8);
-    deserialize_primitive!(i16: deserialize_i16 => visit_i16);
-    deserialize_primitive!(i32: deserialize_i32 => visit_i32);
-    deserialize_primitive!(i64: deserialize_i64 => visit_i64);
-
-    deserialize_primitive!(u8: deserialize_u8 => visit_u8);
-    deserialize_primitive!(u16: deserialize_u16 => visit_u16);
-    deserialize_primitive!(u32: deserialize_u32 => visit_u32);
-    deserialize_primitive!(u64: deserialize_u64 => visit_u64);
-
-    serde_if_integer128! {
-        deserialize_primitive!(i128: deserialize_i128 => visit_i128);
-        deserialize_primitive!(u128: deserialize_u128 => visit_u128);
-    }
-
-    deserialize_primitive!(f32: deserialize_f32 => visit_f32);
-    deserialize_primitive!(f64: deserialize_f64 => visit_f64);
-    deserialize_primitive!(char: deserialize_char => visit_char);
-
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_some(self)
-    }
-
-    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        self.deserialize_byte_buf(visitor)
-    }
-
-    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         match self.item {
-            EntryData::Scalar(item) => {
-                let buf = hex::decode(item).map_err::<Error, _>(|_| {
-                    de::Error::invalid_value(de::Unexpected::Str(item), &visitor)
-                })?;
-                visitor.visit_byte_buf(buf)
-            }
-            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
         }
     }
 
@@ -193,7 +115,7 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, EntryData<'de>> {
     where
         V: Visitor<'de>,
     {
-        match self.item {
+        match self.item()? {
             EntryData::Scalar(input) => Err(de::Error::invalid_type(
                 de::Unexpected::Str(input),
                 &visitor,
@@ -225,17 +147,17 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, EntryData<'de>> {
         self.deserialize_map(visitor)
     }
 
-    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        self.deserialize_string(visitor)
-    }
-
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         visitor.visit_unit()
     }
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
 }
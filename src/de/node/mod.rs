@@ -1,22 +1,32 @@
 use crate::elements::*;
 use crate::iter::*;
+use crate::Error;
+use serde::de;
 
 mod entry_data;
-mod str;
-
-pub use self::entry_data::*;
-pub use self::str::*;
 
 pub struct NodeDeserializer<'a, T> {
     iter: NodeIter<'a, T>,
-    item: &'a T,
+    item: Option<&'a T>,
+    index: usize,
+    many: bool,
 }
 
 impl<'a, T> NodeDeserializer<'a, T> {
     pub(crate) fn new(n: &'a Node<T>) -> Self {
         NodeDeserializer {
             iter: n.iter(),
-            item: n.unique(),
+            item: n.iter().next(),
+            index: 0,
+            many: matches!(n, Node::Many(_)),
         }
     }
 }
+
+impl<'a, T> NodeDeserializer<'a, T> {
+    /// Gets the first value of the node, or an error if the node has no values.
+    fn item(&self) -> Result<&'a T, Error> {
+        self.item
+            .ok_or_else(|| de::Error::invalid_length(0, &"at least one value"))
+    }
+}
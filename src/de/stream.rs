@@ -0,0 +1,955 @@
+use super::ValueDeserializer;
+use crate::parse::{self, DatError, IResult, MAX_DEPTH};
+use crate::Error;
+
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::result::Result as CoreResult;
+use indexmap::IndexMap;
+use nom::{
+    branch::alt,
+    character::complete::{char, multispace0, space0},
+    multi::{many0_count, many1_count},
+    Err,
+};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+type Result<T> = CoreResult<T, Error>;
+
+/// A deserializer that reads a ListInfo DAT directly from its input, without parsing it into a
+/// `DatDocument` first.
+///
+/// Values are deserialized as they are parsed, so only the deserialized type is allocated,
+/// besides the positions of fragments and values that are parsed ahead of their key.
+/// Values of keys that are repeated within a fragment or sub-entry, and fragments with the same
+/// key, are deserialized together as they are with a parsed document. Repeated keys that are not
+/// next to each other are found by parsing ahead, and the fragments and values with other keys
+/// that are passed are kept until their key is deserialized, so each is only parsed ahead once.
+/// A sub-entry is parsed to find where it ends and again as its entries are deserialized, so
+/// entries nested within `n` sub-entries are parsed `n + 1` times.
+///
+/// Values that do not contain escape sequences are borrowed from the input, while values that
+/// do are copied, and so can not be deserialized into `&str`.
+///
+/// Syntax errors are reported as they are by `parse::parse_document`, but a value that can not
+/// be deserialized may be reported before a syntax error that follows it.
+pub struct StreamDeserializer<'de> {
+    input: &'de str,
+    /// The keys that have been deserialized in the document and in the fragments and
+    /// sub-entries that are being deserialized.
+    seen: Vec<&'de str>,
+    /// The input from the fragment that is being parsed, which syntax errors are reported in.
+    fragment: &'de str,
+}
+
+impl<'de> StreamDeserializer<'de> {
+    /// Creates a deserializer for the string contents of a ListInfo DAT.
+    pub fn new(input: &'de str) -> Self {
+        StreamDeserializer {
+            input,
+            seen: Vec::new(),
+            fragment: input,
+        }
+    }
+
+    fn parsed<T>(&self, result: IResult<'de, T>) -> Result<(&'de str, T)> {
+        result.map_err(|err| self.syntax_error(err))
+    }
+
+    /// Reports a syntax error within the fragment that is being parsed.
+    fn syntax_error(&self, err: Err<DatError<'de>>) -> Error {
+        parse::to_error(err, self.input, self.fragment)
+    }
+}
+
+/// A value or sub-entry that has been parsed, but not deserialized.
+enum Token<'de> {
+    Scalar(Cow<'de, str>),
+    /// The input following the opening parenthesis of the sub-entry.
+    SubEntry(&'de str),
+}
+
+/// Parses a value or a sub-entry nested `depth` levels deep, like the document parser,
+/// without collecting the entries of a sub-entry.
+fn entry(input: &str, depth: usize) -> IResult<'_, (&str, Token<'_>)> {
+    alt((|input| sub_entry(input, depth), scalar))(input)
+}
+
+fn sub_entry(input: &str, depth: usize) -> IResult<'_, (&str, Token<'_>)> {
+    let (input, _) = multispace0(input)?;
+    let (input, key) = parse::string_key(input)?;
+    let (input, _) = space0(input)?;
+    let (body, _) = char('(')(input)?;
+    if depth > MAX_DEPTH {
+        return Err(Err::Failure(DatError::too_deep(input)));
+    }
+    let (input, _) = many1_count(|input| entry(input, depth + 1))(body)?;
+    let (input, _) = parse::close_entry(input)?;
+    Ok((input, (key, Token::SubEntry(body))))
+}
+
+fn scalar(input: &str) -> IResult<'_, (&str, Token<'_>)> {
+    let (input, _) = multispace0(input)?;
+    let (input, key) = parse::string_key(input)?;
+    let (input, _) = parse::separator(input)?;
+    let (input, value) = alt((parse::quoted_string, parse::unquoted_string))(input)?;
+    Ok((input, (key, Token::Scalar(parse::trim(value)))))
+}
+
+/// Parses the closing parenthesis of a fragment, after its entries.
+///
+/// The fragment may not be closed because an entry failed to parse, in which case the
+/// entry error is reported as it is by the document parser.
+fn close_fragment(input: &str) -> IResult<'_, char> {
+    match parse::close_entry(input) {
+        Err(Err::Error(close_err)) => Err(Err::Error(match entry(input, 1) {
+            Err(Err::Error(entry_err)) => nom::error::ParseError::or(close_err, entry_err),
+            _ => close_err,
+        })),
+        closed => closed,
+    }
+}
+
+/// Parses the fragment at the start of the input without deserializing it.
+fn skip_fragment(input: &str) -> IResult<'_, ()> {
+    let (input, _) = parse::string_key(input)?;
+    let (input, _) = parse::open_entry(input)?;
+    let (input, _) = many0_count(|input| entry(input, 1))(input)?;
+    let (input, _) = close_fragment(input)?;
+    Ok((input, ()))
+}
+
+/// Finds the `name` of the fragment at the start of the input, which is kept with the paths of
+/// errors within it.
+fn fragment_name(input: &str) -> Option<Cow<'_, str>> {
+    let (input, _) = parse::string_key(input).ok()?;
+    let (mut input, _) = parse::open_entry(input).ok()?;
+    while let Ok((rest, (key, token))) = entry(input, 1) {
+        if key == "name" {
+            return match token {
+                Token::Scalar(name) => Some(name),
+                Token::SubEntry(_) => None,
+            };
+        }
+        input = rest;
+    }
+    None
+}
+
+impl<'de> Deserializer<'de> for &mut StreamDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let input = parse::skip_bom(self.input);
+        let mut access = DocumentAccess::new(self, input);
+        let value = visitor.visit_map(&mut access)?;
+        access.end()?;
+        Ok(value)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct struct map enum identifier ignored_any
+    }
+}
+
+/// The progress of the forward scan over the fragments of a document, or over the entries of a
+/// fragment or sub-entry.
+///
+/// Items with the key that is being deserialized are found by scanning forward. Items with other
+/// keys that have not been deserialized yet are kept as the scan passes them, so that no item has
+/// to be parsed more than once to find the items of its key.
+struct Scan<'de, T> {
+    /// The input from the first item that has not been scanned.
+    frontier: &'de str,
+    /// The items that were passed before their key was deserialized, by key in the order of
+    /// their first items.
+    ahead: IndexMap<&'de str, VecDeque<T>>,
+    /// The items with the key that is being deserialized that were passed before it was.
+    queued: VecDeque<T>,
+}
+
+impl<'de, T> Scan<'de, T> {
+    fn new(input: &'de str) -> Self {
+        Scan {
+            frontier: input,
+            ahead: IndexMap::new(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// Keeps an item that was passed, unless its key has been deserialized already.
+    fn pass(&mut self, seen: &[&'de str], key: &'de str, item: T) {
+        if !seen.contains(&key) {
+            self.ahead.entry(key).or_default().push_back(item);
+        }
+    }
+
+    /// Starts deserializing the first key that was passed, queueing its items.
+    fn next_passed(&mut self) -> Option<&'de str> {
+        let key = *self.ahead.keys().next()?;
+        self.queued = self.ahead.shift_remove(key).unwrap_or_default();
+        Some(key)
+    }
+}
+
+/// Reads the keys of the fragments in a document.
+struct DocumentAccess<'a, 'de> {
+    de: &'a mut StreamDeserializer<'de>,
+    scan: Scan<'de, &'de str>,
+    seen_start: usize,
+    /// Whether the document has a fragment.
+    yielded: bool,
+    key: Option<&'de str>,
+}
+
+impl<'a, 'de> DocumentAccess<'a, 'de> {
+    fn new(de: &'a mut StreamDeserializer<'de>, input: &'de str) -> Self {
+        let seen_start = de.seen.len();
+        DocumentAccess {
+            de,
+            scan: Scan::new(input),
+            seen_start,
+            yielded: false,
+            key: None,
+        }
+    }
+
+    /// Finds the next fragment with the key, returning its input and whether it was found at
+    /// the frontier of the scan.
+    fn find(&mut self, key: &str) -> Result<Option<(&'de str, bool)>> {
+        if let Some(fragment) = self.scan.queued.pop_front() {
+            return Ok(Some((fragment, false)));
+        }
+        loop {
+            let (input, _) = self.de.parsed(multispace0(self.scan.frontier))?;
+            if input.is_empty() {
+                self.scan.frontier = input;
+                return Ok(None);
+            }
+            self.de.fragment = input;
+            let (_, found) = self.de.parsed(parse::string_key(input))?;
+            if found == key {
+                return Ok(Some((input, true)));
+            }
+            let (rest, _) = self.de.parsed(skip_fragment(input))?;
+            self.scan
+                .pass(&self.de.seen[self.seen_start..], found, input);
+            self.scan.frontier = rest;
+        }
+    }
+
+    /// Deserializes the fragment at the start of the input, moving the frontier of the scan
+    /// past it if it was found there.
+    fn deserialize_fragment<R>(
+        &mut self,
+        (input, frontier): (&'de str, bool),
+        deserialize: impl FnOnce(FragmentDeserializer<'_, 'de>) -> Result<R>,
+    ) -> Result<R> {
+        let mut rest = input;
+        let value = deserialize(FragmentDeserializer {
+            de: &mut *self.de,
+            input,
+            rest: &mut rest,
+        })?;
+        if frontier {
+            self.scan.frontier = rest;
+        }
+        Ok(value)
+    }
+
+    /// Parses the fragments that were not deserialized.
+    fn end(&mut self) -> Result<()> {
+        while self.next_key::<IgnoredAny>()?.is_some() {
+            self.next_value::<IgnoredAny>()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'de> Drop for DocumentAccess<'a, 'de> {
+    fn drop(&mut self) {
+        self.de.seen.truncate(self.seen_start);
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for DocumentAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let key = match self.scan.next_passed() {
+            Some(key) => key,
+            None => loop {
+                let (input, _) = self.de.parsed(multispace0(self.scan.frontier))?;
+                self.scan.frontier = input;
+                // A document must contain at least one fragment.
+                if input.is_empty() && self.yielded {
+                    return Ok(None);
+                }
+                self.de.fragment = input;
+                let (_, key) = self.de.parsed(parse::string_key(input))?;
+                self.yielded = true;
+                if !self.de.seen[self.seen_start..].contains(&key) {
+                    break key;
+                }
+                let (rest, _) = self.de.parsed(skip_fragment(input))?;
+                self.scan.frontier = rest;
+            },
+        };
+        self.de.seen.push(key);
+        self.key = Some(key);
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(Error::SerdeError("value is missing".to_string())),
+        };
+        seed.deserialize(FragmentsDeserializer { access: self, key })
+            .map_err(|e| e.at_key(key))
+    }
+}
+
+/// Deserializes the fragments of the document with the same key.
+struct FragmentsDeserializer<'a, 'b, 'de> {
+    access: &'a mut DocumentAccess<'b, 'de>,
+    key: &'de str,
+}
+
+impl<'a, 'b, 'de> Deserializer<'de> for FragmentsDeserializer<'a, 'b, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(FragmentsAccess {
+            access: self.access,
+            key: self.key,
+            index: 0,
+        })
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.access.find(self.key)? {
+            Some(found) => self
+                .access
+                .deserialize_fragment(found, |de| de.deserialize_map(visitor)),
+            None => Err(Error::SerdeError("value is missing".to_string())),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf enum identifier ignored_any
+    }
+}
+
+/// Reads the fragments of the document with the same key.
+struct FragmentsAccess<'a, 'b, 'de> {
+    access: &'a mut DocumentAccess<'b, 'de>,
+    key: &'de str,
+    index: usize,
+}
+
+impl<'a, 'b, 'de> SeqAccess<'de> for FragmentsAccess<'a, 'b, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let found = match self.access.find(self.key)? {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        self.index += 1;
+        self.access
+            .deserialize_fragment(found, |de| seed.deserialize(de))
+            .map(Some)
+            .map_err(|e| e.at_index(index))
+    }
+}
+
+/// Deserializes a single fragment.
+struct FragmentDeserializer<'a, 'de> {
+    de: &'a mut StreamDeserializer<'de>,
+    /// The input from the key of the fragment.
+    input: &'de str,
+    /// Set to the input following the fragment once it has been deserialized.
+    rest: &'a mut &'de str,
+}
+
+impl<'a, 'de> Deserializer<'de> for FragmentDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.fragment = self.input;
+        let (input, _) = self.de.parsed(parse::string_key(self.input))?;
+        let (body, _) = self.de.parsed(parse::open_entry(input))?;
+        let mut access = EntriesAccess::new(self.de, body, 1);
+        let value = visitor
+            .visit_map(&mut access)
+            .and_then(|value| Ok((value, access.end()?)));
+        match value {
+            Ok((value, rest)) => {
+                *self.rest = rest;
+                Ok(value)
+            }
+            Err(err) => Err(err.in_fragment(fragment_name(self.input).as_deref())),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Reads the keys of the entries in a fragment or sub-entry.
+struct EntriesAccess<'a, 'de> {
+    de: &'a mut StreamDeserializer<'de>,
+    scan: Scan<'de, Token<'de>>,
+    /// The depth of sub-entries within the entries.
+    depth: usize,
+    seen_start: usize,
+    pending: Option<(&'de str, Token<'de>)>,
+    /// The input following the closing parenthesis, once every entry has been read.
+    end: Option<&'de str>,
+}
+
+impl<'a, 'de> EntriesAccess<'a, 'de> {
+    fn new(de: &'a mut StreamDeserializer<'de>, input: &'de str, depth: usize) -> Self {
+        let seen_start = de.seen.len();
+        EntriesAccess {
+            de,
+            scan: Scan::new(input),
+            depth,
+            seen_start,
+            pending: None,
+            end: None,
+        }
+    }
+
+    /// Finds the next value with the key, scanning forward if none was passed before.
+    fn find(&mut self, key: &str) -> Result<Option<Token<'de>>> {
+        if let Some(token) = self.scan.queued.pop_front() {
+            return Ok(Some(token));
+        }
+        loop {
+            match entry(self.scan.frontier, self.depth) {
+                Ok((rest, (found, token))) => {
+                    self.scan.frontier = rest;
+                    if found == key {
+                        return Ok(Some(token));
+                    }
+                    self.scan
+                        .pass(&self.de.seen[self.seen_start..], found, token);
+                }
+                Err(Err::Error(_)) => return Ok(None),
+                Err(err) => return Err(self.de.syntax_error(err)),
+            }
+        }
+    }
+
+    /// Whether the key has another value.
+    fn repeated(&mut self, key: &str) -> Result<bool> {
+        if !self.scan.queued.is_empty() {
+            return Ok(true);
+        }
+        match self.find(key)? {
+            Some(token) => {
+                self.scan.queued.push_back(token);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Parses the entries that were not deserialized, returning the input following the
+    /// closing parenthesis.
+    fn end(&mut self) -> Result<&'de str> {
+        loop {
+            if let Some(rest) = self.end {
+                return Ok(rest);
+            }
+            if self.next_key::<IgnoredAny>()?.is_some() {
+                self.next_value::<IgnoredAny>()?;
+            }
+        }
+    }
+}
+
+impl<'a, 'de> Drop for EntriesAccess<'a, 'de> {
+    fn drop(&mut self) {
+        self.de.seen.truncate(self.seen_start);
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for EntriesAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let passed = self
+            .scan
+            .next_passed()
+            .and_then(|key| Some((key, self.scan.queued.pop_front()?)));
+        let (key, token) = match passed {
+            Some(passed) => passed,
+            None => loop {
+                if self.end.is_some() {
+                    return Ok(None);
+                }
+                match entry(self.scan.frontier, self.depth) {
+                    Ok((rest, (key, token))) => {
+                        self.scan.frontier = rest;
+                        if !self.de.seen[self.seen_start..].contains(&key) {
+                            break (key, token);
+                        }
+                    }
+                    Err(Err::Error(_)) => {
+                        let close = if self.depth == 1 {
+                            close_fragment(self.scan.frontier)
+                        } else {
+                            parse::close_entry(self.scan.frontier)
+                        };
+                        let (rest, _) = self.de.parsed(close)?;
+                        self.end = Some(rest);
+                    }
+                    Err(err) => return Err(self.de.syntax_error(err)),
+                }
+            },
+        };
+        self.de.seen.push(key);
+        self.pending = Some((key, token));
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let (key, token) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Err(Error::SerdeError("value is missing".to_string())),
+        };
+        seed.deserialize(ValuesDeserializer {
+            access: self,
+            key,
+            first: token,
+        })
+        .map_err(|e| e.at_key(key))
+    }
+}
+
+/// Deserializes the values of a fragment or sub-entry with the same key.
+struct ValuesDeserializer<'a, 'b, 'de> {
+    access: &'a mut EntriesAccess<'b, 'de>,
+    key: &'de str,
+    first: Token<'de>,
+}
+
+impl<'a, 'b, 'de> ValuesDeserializer<'a, 'b, 'de> {
+    fn first(self) -> TokenDeserializer<'a, 'de> {
+        TokenDeserializer {
+            de: &mut *self.access.de,
+            token: self.first,
+            depth: self.access.depth,
+        }
+    }
+}
+
+/// Forwards methods to the deserializer of the first value.
+macro_rules! forward_to_first {
+    ($($deserialize:ident)*) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.first().$deserialize(visitor)
+            }
+        )*
+    };
+}
+
+impl<'a, 'b, 'de> Deserializer<'de> for ValuesDeserializer<'a, 'b, 'de> {
+    type Error = Error;
+
+    /// Deserializes repeated keys as a sequence, and a single value as itself.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.access.repeated(self.key)? {
+            self.deserialize_seq(visitor)
+        } else {
+            self.first().deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(ValuesAccess {
+            access: self.access,
+            key: self.key,
+            first: Some(self.first),
+            index: 0,
+        })
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.first().deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.first().deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_first! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_map
+        deserialize_identifier
+    }
+}
+
+/// Reads the values of a fragment or sub-entry with the same key.
+struct ValuesAccess<'a, 'b, 'de> {
+    access: &'a mut EntriesAccess<'b, 'de>,
+    key: &'de str,
+    first: Option<Token<'de>>,
+    index: usize,
+}
+
+impl<'a, 'b, 'de> SeqAccess<'de> for ValuesAccess<'a, 'b, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let token = match self.first.take() {
+            Some(token) => token,
+            None => match self.access.find(self.key)? {
+                Some(token) => token,
+                None => return Ok(None),
+            },
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(TokenDeserializer {
+            de: &mut *self.access.de,
+            token,
+            depth: self.access.depth,
+        })
+        .map(Some)
+        .map_err(|e| e.at_index(index))
+    }
+}
+
+/// Deserializes a single value or sub-entry.
+struct TokenDeserializer<'a, 'de> {
+    de: &'a mut StreamDeserializer<'de>,
+    token: Token<'de>,
+    /// The depth of the sub-entry.
+    depth: usize,
+}
+
+impl<'a, 'de> TokenDeserializer<'a, 'de> {
+    /// Gets the deserializer of a value, or an error if this is a sub-entry.
+    fn scalar(self, expected: &dyn de::Expected) -> Result<ValueDeserializer<'de>> {
+        match self.token {
+            Token::Scalar(item) => Ok(ValueDeserializer::new(item)),
+            Token::SubEntry(_) => Err(Error::unexpected_nesting(expected)),
+        }
+    }
+
+    fn visit_sub_entry<V>(self, body: &'de str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(EntriesAccess::new(self.de, body, self.depth + 1))
+    }
+}
+
+impl<'a, 'de> Deserializer<'de> for TokenDeserializer<'a, 'de> {
+    type Error = Error;
+
+    /// Deserializes values as strings and sub-entries as maps.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.token {
+            Token::Scalar(_) => self.deserialize_str(visitor),
+            Token::SubEntry(body) => self.visit_sub_entry(body, visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.token {
+            Token::Scalar(item) => Err(de::Error::invalid_type(
+                de::Unexpected::Str(&item),
+                &visitor,
+            )),
+            Token::SubEntry(body) => self.visit_sub_entry(body, visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+        deserialize_seq
+    }
+}
@@ -2,20 +2,23 @@ use crate::elements::*;
 use crate::iter::*;
 use crate::Error;
 
+use alloc::borrow::Cow;
 use alloc::string::ToString;
 use core::result::Result as CoreResult;
+use serde::de::value::BorrowedStrDeserializer;
 use serde::de::{DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor};
 use serde::forward_to_deserialize_any;
 
 type Result<T> = CoreResult<T, Error>;
 
+/// A deserializer for a sub-entry, which may contain further nested sub-entries.
 pub struct SubEntryDeserializer<'de> {
-    iter: EntryIter<'de, &'de str, Node<&'de str>>,
-    value: Option<&'de Node<&'de str>>,
+    iter: EntryIter<'de, Cow<'de, str>, Node<EntryData<'de>>>,
+    value: Option<(&'de str, &'de Node<EntryData<'de>>)>,
 }
 
 impl<'de> SubEntryDeserializer<'de> {
-    pub(crate) fn new(iter: EntryIter<'de, &'de str, Node<&'de str>>) -> Self {
+    pub(crate) fn new(iter: EntryIter<'de, Cow<'de, str>, Node<EntryData<'de>>>) -> Self {
         SubEntryDeserializer { iter, value: None }
     }
 }
@@ -29,8 +32,8 @@ impl<'de> MapAccess<'de> for SubEntryDeserializer<'de> {
     {
         match self.iter.next() {
             Some((key, value)) => {
-                self.value = Some(value);
-                let key_de = key.into_deserializer();
+                self.value = Some((key, value));
+                let key_de = BorrowedStrDeserializer::<Error>::new(key);
                 seed.deserialize(key_de).map(Some)
             }
             None => Ok(None),
@@ -42,7 +45,9 @@ impl<'de> MapAccess<'de> for SubEntryDeserializer<'de> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(value.into_deserializer()),
+            Some((key, value)) => seed
+                .deserialize(value.into_deserializer())
+                .map_err(|e| e.at_key(key)),
             None => Err(crate::Error::SerdeError("value is missing".to_string())),
         }
     }
@@ -1,4 +1,5 @@
 #![cfg(feature = "test_deserialize")]
+#![allow(dead_code)]
 use alloc::string::String;
 use serde::Deserialize;
 
@@ -75,3 +76,475 @@ fn deserialize_cave_story() {
         &[0xc5, 0xa2, 0xa3, 0xf6]
     );
 }
+
+#[cfg(feature = "logiqx")]
+#[test]
+fn deserialize_logiqx_document() {
+    const CAVE_STORY: &str = r#"<?xml version="1.0"?>
+        <datafile>
+            <header>
+                <name>Cave Story</name>
+                <description>Cave Story</description>
+                <version>20161204</version>
+                <comment>libretro | www.libretro.com</comment>
+            </header>
+            <game name="Cave Story (En)">
+                <description>Cave Story (En)</description>
+                <developer>Studio Pixel</developer>
+                <releaseyear>2004</releaseyear>
+                <rom name="Doukutsu.exe" size="1478656" crc="c5a2a3f6"
+                    md5="38695d3d69d7a0ada8178072dad4c58b" sha1="bb2d0441e073da9c584f23c2ad8c7ab8aac293bf"/>
+            </game>
+        </datafile>"#;
+
+    let document = crate::logiqx::parse_document(CAVE_STORY).unwrap();
+    let cave_story = super::from_document::<CaveStory>(&document).unwrap();
+    assert_eq!(cave_story.clrmamepro.version, "20161204");
+    assert_eq!(cave_story.game[0].releaseyear, 2004);
+    assert_eq!(cave_story.game[0].rom[0].size, 1478656);
+    assert_eq!(cave_story.game[0].rom[0].crc, &[0xc5, 0xa2, 0xa3, 0xf6]);
+}
+
+#[test]
+fn deserialize_escaped_strings() {
+    #[derive(Debug, Deserialize)]
+    struct Game<'a> {
+        name: String,
+        description: &'a str,
+    }
+
+    const GAME: &str = r#"game (
+                name "Cave Story \"Doukutsu Monogatari\" (En)"
+                description "Cave \\ Story \"(En)\""
+            )"#;
+
+    let (_, fragment) = crate::parse::parse_fragment(GAME).unwrap();
+    let game = super::from_fragment::<Game>(&fragment).unwrap();
+    assert_eq!(game.name, "Cave Story \"Doukutsu Monogatari\" (En)");
+    assert_eq!(game.description, "Cave \\ Story \"(En)\"");
+}
+
+#[test]
+fn deserialize_windows_line_endings() {
+    const CAVE_STORY: &str = "clrmamepro (\r\n\tname \"Cave Story\"\r\n\tdescription \"Cave Story\"\r\n\tversion 20161204\r\n\tcomment \"libretro | www.libretro.com\"\r\n)\r\n\r\ngame (\r\n\tname \"Cave Story (En)\"\r\n\tdeveloper\t\"Studio Pixel\"\r\n\treleaseyear 2004\r\n\trom ( name Doukutsu.exe size 1478656 crc c5a2a3f6 md5 38695d3d69d7a0ada8178072dad4c58b sha1 bb2d0441e073da9c584f23c2ad8c7ab8aac293bf )\r\n)\r\n";
+
+    let cave_story = super::from_str::<CaveStory>(CAVE_STORY).unwrap();
+    assert_eq!(cave_story.game[0].releaseyear, 2004);
+    assert_eq!(cave_story.game[0].rom[0].crc, &[0xc5, 0xa2, 0xa3, 0xf6]);
+}
+
+#[test]
+fn deserialize_hashes() {
+    use crate::hash::{Crc32, Sha1};
+
+    #[derive(Debug, Deserialize)]
+    struct Rom {
+        name: String,
+        crc: Crc32,
+        sha1: Option<Sha1>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Game {
+        rom: Vec<Rom>,
+    }
+
+    const GAME: &str = r#"game (
+                rom ( name a.bin crc C5A2A3F6 sha1 bb2d0441e073da9c584f23c2ad8c7ab8aac293bf )
+                rom ( name b.bin crc 08d8daa5 )
+            )"#;
+
+    let (_, fragment) = crate::parse::parse_fragment(GAME).unwrap();
+    let game = super::from_fragment::<Game>(&fragment).unwrap();
+    assert_eq!(game.rom[0].crc, Crc32([0xc5, 0xa2, 0xa3, 0xf6]));
+    assert!(game.rom[0].sha1.is_some());
+    assert_eq!(game.rom[1].crc, Crc32::from(0x08d8daa5));
+    assert_eq!(game.rom[1].sha1, None);
+
+    let (_, fragment) =
+        crate::parse::parse_fragment("game ( rom ( name c.bin crc 08d8da ) )").unwrap();
+    assert!(super::from_fragment::<Game>(&fragment).is_err());
+}
+
+#[test]
+fn deserialize_nested_sub_entries() {
+    #[derive(Debug, Deserialize)]
+    struct Color {
+        depth: u8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Emulation {
+        state: String,
+        color: Option<Color>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Driver {
+        status: String,
+        emulation: Vec<Emulation>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Game {
+        name: String,
+        driver: Driver,
+    }
+
+    const GAME: &str = r#"game (
+                name "Nested"
+                driver ( status good emulation ( state good color ( depth 8 ) ) emulation ( state preliminary ) )
+            )"#;
+
+    let (_, fragment) = crate::parse::parse_fragment(GAME).unwrap();
+    let game = super::from_fragment::<Game>(&fragment).unwrap();
+    assert_eq!(game.driver.status, "good");
+    assert_eq!(game.driver.emulation.len(), 2);
+    assert_eq!(game.driver.emulation[0].color.as_ref().unwrap().depth, 8);
+    assert_eq!(game.driver.emulation[1].state, "preliminary");
+
+    #[derive(Debug, Deserialize)]
+    struct Flat {
+        driver: String,
+    }
+    assert!(super::from_fragment::<Flat>(&fragment).is_err());
+}
+
+#[test]
+fn deserialize_owned_document() {
+    #[derive(Debug, Deserialize)]
+    struct Game {
+        name: String,
+        rom: Vec<Rom>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Rom {
+        name: String,
+        size: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Document {
+        game: Vec<Game>,
+    }
+
+    let owned: crate::OwnedDatDocument = {
+        let input = String::from("game ( name a rom ( name a.bin size 1 ) )");
+        crate::parse::parse_document(&input).unwrap().into_owned()
+    };
+    let document = super::from_document::<Document>(&owned).unwrap();
+    assert_eq!(document.game[0].name, "a");
+    assert_eq!(document.game[0].rom[0].size, 1);
+}
+
+#[test]
+fn deserialize_self_describing() {
+    use alloc::collections::BTreeMap;
+    use alloc::vec::Vec;
+    use serde_json::json;
+
+    const DAT: &str = r#"clrmamepro ( name "Cave Story" )
+            game (
+                name "Cave Story (En)"
+                releaseyear 2004
+                rom ( name Doukutsu.exe size 1478656 )
+                rom ( name data.bin size 1 )
+                driver ( status good emulation ( state good ) )
+            )"#;
+
+    let value = super::from_str::<serde_json::Value>(DAT).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "clrmamepro": [{ "name": "Cave Story" }],
+            "game": [{
+                "name": "Cave Story (En)",
+                "releaseyear": "2004",
+                "rom": [
+                    { "name": "Doukutsu.exe", "size": "1478656" },
+                    { "name": "data.bin", "size": "1" }
+                ],
+                "driver": { "status": "good", "emulation": { "state": "good" } }
+            }]
+        })
+    );
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Scalar(String),
+        Many(Vec<Value>),
+        SubEntry(BTreeMap<String, Value>),
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Rom {
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Game {
+        name: String,
+        rom: Vec<Rom>,
+        #[serde(flatten)]
+        rest: BTreeMap<String, Value>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Document {
+        game: Vec<Game>,
+    }
+
+    let document = super::from_str::<Document>(DAT).unwrap();
+    let game = &document.game[0];
+    assert_eq!(game.name, "Cave Story (En)");
+    assert_eq!(game.rom[1].name, "data.bin");
+    assert_eq!(
+        game.rest.get("releaseyear"),
+        Some(&Value::Scalar("2004".into()))
+    );
+    let mut emulation = BTreeMap::new();
+    emulation.insert(String::from("state"), Value::Scalar("good".into()));
+    let mut driver = BTreeMap::new();
+    driver.insert(String::from("status"), Value::Scalar("good".into()));
+    driver.insert(String::from("emulation"), Value::SubEntry(emulation));
+    assert_eq!(game.rest.get("driver"), Some(&Value::SubEntry(driver)));
+    assert_eq!(game.rest.len(), 2);
+}
+
+#[test]
+fn deserialize_error_paths() {
+    use crate::error::{Error, PathSegment};
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Deserialize)]
+    struct Rom {
+        name: String,
+        size: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Game {
+        name: String,
+        rom: Vec<Rom>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Document {
+        game: Vec<Game>,
+    }
+
+    const INVALID_TYPE: &str = r#"game ( name "A" rom ( name a size 1 ) )
+game ( name "B" rom ( name b size 1 ) rom ( name c size big ) )"#;
+    let err = super::from_str::<Document>(INVALID_TYPE).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"game[1] (name "B") → rom[1].size: invalid type: string "big", expected u64"#
+    );
+    assert_eq!(
+        err.path().map(ToString::to_string).as_deref(),
+        Some(r#"game[1] (name "B") → rom[1].size"#)
+    );
+    match err {
+        Error::InvalidType { path, .. } => {
+            assert_eq!(path.fragment_name(), Some("B"));
+            assert_eq!(
+                path.segments(),
+                &[
+                    PathSegment::Key("game".into()),
+                    PathSegment::Index(1),
+                    PathSegment::Key("rom".into()),
+                    PathSegment::Index(1),
+                    PathSegment::Key("size".into()),
+                ]
+            );
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    const MISSING_FIELD: &str = r#"game ( name "A" rom ( name a ) )"#;
+    let err = super::from_str::<Document>(MISSING_FIELD).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"game[0] (name "A") → rom[0]: missing field `size`"#
+    );
+    assert!(matches!(err, Error::MissingField { field: "size", .. }));
+
+    const NESTED: &str = r#"game ( name ( en "A" ) rom ( name a size 1 ) )"#;
+    let err = super::from_str::<Document>(NESTED).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "game[0].name: expected a string, found a sub-entry"
+    );
+    assert!(matches!(err, Error::UnexpectedNesting { .. }));
+
+    #[derive(Debug, Deserialize)]
+    struct Header {
+        name: String,
+        version: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Dat {
+        clrmamepro: Header,
+    }
+
+    const HEADER: &str = r#"clrmamepro ( name "Nintendo - Game Boy" version 2020-01-01 )"#;
+    let err = super::from_str::<Dat>(HEADER).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"clrmamepro (name "Nintendo - Game Boy") → version: invalid type: string "2020-01-01", expected u32"#
+    );
+}
+
+#[test]
+fn deserialize_borrowed() {
+    use alloc::borrow::Cow;
+    use alloc::collections::BTreeMap;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Deserialize)]
+    struct Rom<'a> {
+        name: &'a str,
+        #[serde(borrow)]
+        crc: Cow<'a, str>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Game<'a> {
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+        description: &'a str,
+        #[serde(borrow)]
+        rom: Vec<Rom<'a>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Dat<'a> {
+        #[serde(borrow)]
+        game: Vec<Game<'a>>,
+    }
+
+    const DAT: &str = r#"game (
+        name "Cave Story \"En\""
+        description "Cave Story (En)"
+        rom ( name Doukutsu.exe crc c5a2a3f6 )
+    )"#;
+
+    let document = super::Document::parse(DAT).unwrap();
+    let dat = document.deserialize::<Dat<'_>>().unwrap();
+    let game = &dat.game[0];
+    assert_eq!(game.name, r#"Cave Story "En""#);
+    assert_eq!(game.description, "Cave Story (En)");
+    assert!(crate::span::byte_range(DAT, game.description).is_some());
+    assert!(crate::span::byte_range(DAT, game.rom[0].name).is_some());
+    assert!(matches!(game.rom[0].crc, Cow::Borrowed("c5a2a3f6")));
+
+    // Keys borrow from the input as well.
+    let header = super::Document::parse("clrmamepro ( name x version 1 )").unwrap();
+    let header = header
+        .deserialize::<BTreeMap<&str, BTreeMap<&str, &str>>>()
+        .unwrap();
+    assert_eq!(header["clrmamepro"]["version"], "1");
+}
+
+#[test]
+fn deserialize_stream() {
+    use crate::parse::{parse_document, MAX_DEPTH};
+    use alloc::collections::BTreeMap;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    // Interleaved fragments and keys, quoted and escaped values, and nested sub-entries.
+    const DAT: &str = "\u{feff}clrmamepro ( name \"Header\" )
+resource ( name bios rom ( name bios.bin size 1 ) )
+game (
+    name \"Cave \\\"Story\\\"\"
+    rom ( name a.bin size 1 )
+    disk ( name a.chd )
+    sample a
+    rom ( name b.bin size 2 )
+    disk ( name b.chd )
+    driver ( status good emulation ( state good ) )
+    sample b
+)
+resource ( name other )
+machine ( name device )
+game ( name second rom ( name c.bin size 3 ) )
+resource ( name last )
+machine ( name other_device )
+";
+
+    let tree = parse_document(DAT).unwrap();
+    let expected = super::from_document::<serde_json::Value>(&tree).unwrap();
+    assert_eq!(super::from_str::<serde_json::Value>(DAT).unwrap(), expected);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Rom<'a> {
+        name: &'a str,
+        size: u64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Game<'a> {
+        name: String,
+        #[serde(borrow)]
+        rom: Vec<Rom<'a>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Dat<'a> {
+        #[serde(borrow)]
+        game: Vec<Game<'a>>,
+        resource: Vec<BTreeMap<String, serde_json::Value>>,
+        machine: Vec<BTreeMap<String, String>>,
+    }
+
+    let dat = super::from_str::<Dat<'_>>(DAT).unwrap();
+    assert_eq!(dat, super::from_document::<Dat<'_>>(&tree).unwrap());
+    assert_eq!(dat.game[0].name, r#"Cave "Story""#);
+    assert_eq!(
+        dat.game[0].rom[1],
+        Rom {
+            name: "b.bin",
+            size: 2
+        }
+    );
+    assert_eq!(dat.game[1].rom[0].name, "c.bin");
+    assert_eq!(dat.resource.len(), 3);
+    assert_eq!(dat.machine[1]["name"], "other_device");
+
+    // Escaped values are copied, and can not be borrowed.
+    #[derive(Debug, Deserialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
+    }
+    let err = super::from_str::<BTreeMap<&str, Vec<Borrowed<'_>>>>(DAT).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(r#"game[0] (name "Cave "Story"") → name: invalid type: string"#));
+
+    // Syntax errors are reported as they are by the parser.
+    let nested = String::from("game ( ") + &"a ( ".repeat(MAX_DEPTH + 1) + "b 1";
+    let invalid = [
+        "",
+        "game ( name x",
+        "game ( name x ) )",
+        "game ( rom ( name x )",
+        "game ( name a )\ngame ( name b rom ( ) )",
+        "game ( name a )\nresource ( name b )\ngame ( name c\n",
+        "game ( name a rom ( name x ) rom ( name y ) rom ( name \"z )",
+        "game ( name a )\n( name b )",
+        &nested,
+    ];
+    for dat in &invalid {
+        assert_eq!(
+            super::from_str::<serde_json::Value>(dat)
+                .unwrap_err()
+                .to_string(),
+            parse_document(dat).unwrap_err().to_string()
+        );
+    }
+}
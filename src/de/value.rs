@@ -1,88 +1,99 @@
-use super::NodeDeserializer;
 use crate::Error;
-use alloc::string::String;
+use alloc::borrow::Cow;
 use core::result::Result as CoreResult;
 use hex;
-use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, SeqAccess, Visitor};
-use serde::serde_if_integer128;
+use serde::de::{self, Deserializer, Visitor};
 
 type Result<T> = CoreResult<T, Error>;
 
-impl<'de> SeqAccess<'de> for NodeDeserializer<'de, &'de str> {
-    type Error = crate::Error;
-
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        match self.iter.next() {
-            Some(&value) => seed.deserialize(value.into_deserializer()).map(Some),
-            None => Ok(None),
-        }
-    }
-
-    fn size_hint(&self) -> Option<usize> {
-        match self.iter.size_hint() {
-            (lower, Some(upper)) if lower == upper => Some(upper),
-            _ => None,
-        }
-    }
+/// Forwards methods that only accept a value to the `ValueDeserializer` from `self.scalar`.
+macro_rules! forward_to_value {
+    ($($deserialize:ident)*) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.scalar(&visitor)?.$deserialize(visitor)
+            }
+        )*
+    };
 }
 
 macro_rules! deserialize_primitive {
     ($t:ty: $deserialize:ident => $visit:ident) => {
-        fn $deserialize<V: de::Visitor<'de>>(
-            self,
-            visitor: V,
-        ) -> core::result::Result<V::Value, Error> {
-            let value = self.item.parse::<$t>().map_err::<Error, _>(|_| {
-                de::Error::invalid_type(de::Unexpected::Str(self.item), &visitor)
+        fn $deserialize<V>(self, visitor: V) -> Result<V::Value>
+        where
+            V: Visitor<'de>,
+        {
+            let value = self.value.parse::<$t>().map_err::<Error, _>(|_| {
+                de::Error::invalid_type(de::Unexpected::Str(&self.value), &visitor)
             })?;
             visitor.$visit(value)
         }
     };
 }
 
-impl<'de> Deserializer<'de> for NodeDeserializer<'de, &'de str> {
-    type Error = crate::Error;
+/// Deserializes a single value, whether it is part of a parsed document or is being parsed.
+///
+/// Borrowed values are deserialized as borrowed strings, while values that were copied can
+/// only be deserialized as owned strings.
+pub(crate) struct ValueDeserializer<'de> {
+    value: Cow<'de, str>,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    pub(crate) fn new(value: Cow<'de, str>) -> Self {
+        ValueDeserializer { value }
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(*self.item)
+        match self.value {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_string(String::from(*self.item))
+        visitor.visit_string(self.value.into_owned())
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(self)
+        Err(de::Error::invalid_type(
+            de::Unexpected::Str(&self.value),
+            &visitor,
+        ))
     }
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_str(visitor)
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.item {
-            &"yes" => visitor.visit_bool(true),
-            &"no" => visitor.visit_bool(false),
-            _ => visitor.visit_bool(self.item.parse::<bool>().map_err::<Error, _>(|_| {
-                de::Error::invalid_type(de::Unexpected::Str(self.item), &"bool")
+        match self.value.as_ref() {
+            "yes" => visitor.visit_bool(true),
+            "no" => visitor.visit_bool(false),
+            _ => visitor.visit_bool(self.value.parse::<bool>().map_err::<Error, _>(|_| {
+                de::Error::invalid_type(de::Unexpected::Str(&self.value), &"bool")
             })?),
         }
     }
@@ -97,10 +108,8 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, &'de str> {
     deserialize_primitive!(u32: deserialize_u32 => visit_u32);
     deserialize_primitive!(u64: deserialize_u64 => visit_u64);
 
-    serde_if_integer128! {
-        deserialize_primitive!(i128: deserialize_i128 => visit_i128);
-        deserialize_primitive!(u128: deserialize_u128 => visit_u128);
-    }
+    deserialize_primitive!(i128: deserialize_i128 => visit_i128);
+    deserialize_primitive!(u128: deserialize_u128 => visit_u128);
 
     deserialize_primitive!(f32: deserialize_f32 => visit_f32);
     deserialize_primitive!(f64: deserialize_f64 => visit_f64);
@@ -124,8 +133,8 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, &'de str> {
     where
         V: Visitor<'de>,
     {
-        let buf = hex::decode(self.item).map_err::<Error, _>(|_| {
-            de::Error::invalid_value(de::Unexpected::Str(self.item), &visitor)
+        let buf = hex::decode(self.value.as_bytes()).map_err::<Error, _>(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&self.value), &visitor)
         })?;
         visitor.visit_byte_buf(buf)
     }
@@ -175,7 +184,7 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, &'de str> {
         V: Visitor<'de>,
     {
         Err(de::Error::invalid_type(
-            de::Unexpected::Str(self.item),
+            de::Unexpected::Str(&self.value),
             &visitor,
         ))
     }
@@ -191,6 +200,7 @@ impl<'de> Deserializer<'de> for NodeDeserializer<'de, &'de str> {
     {
         self.deserialize_map(visitor)
     }
+
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
@@ -1,16 +1,141 @@
 use crate::iter::*;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
+use indexmap::map::Entry;
 use indexmap::IndexMap;
 
+/// Implements the editing methods shared by `EntryFragment` and `SubEntry`.
+macro_rules! impl_entries_mut {
+    ($ty:ident) => {
+        impl<'a> $ty<'a> {
+            /// Creates an empty instance without any entries.
+            pub fn new() -> Self {
+                $ty {
+                    keys: IndexMap::new(),
+                }
+            }
+
+            /// Appends a value with the given key, returning `self` for building entries in order.
+            pub fn with(
+                mut self,
+                key: impl Into<Cow<'a, str>>,
+                value: impl Into<EntryData<'a>>,
+            ) -> Self {
+                self.push(key, value);
+                self
+            }
+
+            /// Appends a value with the given key.
+            ///
+            /// If the key already exists, the value is added after its existing values.
+            pub fn push(&mut self, key: impl Into<Cow<'a, str>>, value: impl Into<EntryData<'a>>) {
+                let value = value.into();
+                match self.keys.entry(key.into()) {
+                    Entry::Occupied(mut entry) => entry.get_mut().push(value),
+                    Entry::Vacant(entry) => {
+                        entry.insert(Node::Unique(value));
+                    }
+                }
+            }
+
+            /// Sets the value with the given key, replacing any existing values.
+            ///
+            /// An existing key keeps its position, otherwise it is added after the other keys.
+            /// Returns the values that were replaced.
+            pub fn insert(
+                &mut self,
+                key: impl Into<Cow<'a, str>>,
+                value: impl Into<EntryData<'a>>,
+            ) -> Option<Node<EntryData<'a>>> {
+                self.keys.insert(key.into(), Node::Unique(value.into()))
+            }
+
+            /// Removes all values with the given key, keeping the order of the remaining keys.
+            pub fn remove(&mut self, key: &str) -> Option<Node<EntryData<'a>>> {
+                self.keys.shift_remove(key)
+            }
+
+            /// Keeps only the values for which the predicate returns `true`.
+            ///
+            /// Keys that are left without any values are removed.
+            pub fn retain<F: FnMut(&str, &EntryData<'a>) -> bool>(&mut self, mut f: F) {
+                self.keys.retain(|key, node| {
+                    match core::mem::replace(node, Node::Many(Vec::new()))
+                        .retain(|value| f(key, value))
+                    {
+                        Some(kept) => {
+                            *node = kept;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+            }
+
+            /// Gets the values with the given key as a mutable iterator if it exists.
+            ///
+            /// Values are edited in place, so a key always keeps at least one value. Use
+            /// `push`, `insert`, `remove` and `retain` to add or remove values.
+            pub fn entry_mut(
+                &mut self,
+                key: &str,
+            ) -> Option<core::slice::IterMut<'_, EntryData<'a>>> {
+                self.keys.get_mut(key).map(|f| f.iter_mut())
+            }
+
+            /// Renames a key, keeping its position.
+            ///
+            /// If the new key already exists, the values of both keys are merged
+            /// in key order, at the position of whichever key came first.
+            /// Returns `false` if there was no entry with the old key.
+            pub fn rename(&mut self, from: &str, to: impl Into<Cow<'a, str>>) -> bool {
+                if !self.keys.contains_key(from) {
+                    return false;
+                }
+                let to = to.into();
+                let mut keys: IndexMap<Cow<'a, str>, Node<EntryData<'a>>> =
+                    IndexMap::with_capacity(self.keys.len());
+                for (key, node) in self.keys.drain(..) {
+                    let key = if key == from { to.clone() } else { key };
+                    match keys.get_mut(key.as_ref()) {
+                        Some(existing) => {
+                            for value in node.into_vec() {
+                                existing.push(value);
+                            }
+                        }
+                        None => {
+                            keys.insert(key, node);
+                        }
+                    }
+                }
+                self.keys = keys;
+                true
+            }
+        }
+
+        impl<'a> Default for $ty<'a> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+}
+
 /// The contents of a ListInfo DAT file.
-#[derive(Debug)]
+///
+/// Keys and values borrow from the parsed input where possible.
+/// `DatDocument::into_owned` copies them out, producing an `OwnedDatDocument`
+/// that can outlive the input, be cached, or be sent across threads.
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DatDocument<'a> {
-    pub(crate) document: IndexMap<&'a str, Vec<EntryFragment<'a>>>,
+    pub(crate) document: IndexMap<Cow<'a, str>, Vec<EntryFragment<'a>>>,
 }
 
 impl<'a> DatDocument<'a> {
     /// Get DAT entries with the given key as an iterator
-    pub fn entry(&'a self, key: &str) -> Option<impl Iterator<Item = &EntryFragment<'a>>> {
+    pub fn entry(&'a self, key: &str) -> Option<impl Iterator<Item = &'a EntryFragment<'a>>> {
         self.document.get(key).map(|f| f.iter())
     }
 
@@ -18,59 +143,241 @@ impl<'a> DatDocument<'a> {
     pub fn iter(&'a self) -> SliceIter<'a, EntryFragment<'a>> {
         SliceIter::new(self.document.iter())
     }
+
+    /// Copies any keys and values borrowed from the input, so that the document
+    /// no longer borrows from it.
+    pub fn into_owned(self) -> OwnedDatDocument {
+        DatDocument {
+            document: self
+                .document
+                .into_iter()
+                .map(|(key, fragments)| {
+                    (
+                        Cow::Owned(key.into_owned()),
+                        fragments
+                            .into_iter()
+                            .map(EntryFragment::into_owned)
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates an empty document without any fragments.
+    pub fn new() -> Self {
+        DatDocument {
+            document: IndexMap::new(),
+        }
+    }
+
+    /// Appends a fragment with the given key, returning `self` for building documents in order.
+    pub fn with(mut self, key: impl Into<Cow<'a, str>>, fragment: EntryFragment<'a>) -> Self {
+        self.push(key, fragment);
+        self
+    }
+
+    /// Appends a fragment with the given key.
+    ///
+    /// If the key already exists, the fragment is added after its existing fragments.
+    pub fn push(&mut self, key: impl Into<Cow<'a, str>>, fragment: EntryFragment<'a>) {
+        self.document.entry(key.into()).or_default().push(fragment);
+    }
+
+    /// Sets the fragment with the given key, replacing any existing fragments.
+    ///
+    /// Returns the fragments that were replaced.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        fragment: EntryFragment<'a>,
+    ) -> Option<Vec<EntryFragment<'a>>> {
+        self.document.insert(key.into(), vec![fragment])
+    }
+
+    /// Removes all fragments with the given key, keeping the order of the remaining keys.
+    pub fn remove(&mut self, key: &str) -> Option<Vec<EntryFragment<'a>>> {
+        self.document.shift_remove(key)
+    }
+
+    /// Keeps only the fragments for which the predicate returns `true`.
+    ///
+    /// Keys that are left without any fragments are removed.
+    pub fn retain<F: FnMut(&str, &EntryFragment<'a>) -> bool>(&mut self, mut f: F) {
+        self.document.retain(|key, fragments| {
+            fragments.retain(|fragment| f(key, fragment));
+            !fragments.is_empty()
+        });
+    }
+
+    /// Get DAT entries with the given key as a mutable iterator.
+    pub fn entry_mut(&mut self, key: &str) -> Option<core::slice::IterMut<'_, EntryFragment<'a>>> {
+        self.document.get_mut(key).map(|f| f.iter_mut())
+    }
 }
 
+impl<'a> Default for DatDocument<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `DatDocument` that does not borrow from its input.
+pub type OwnedDatDocument = DatDocument<'static>;
+
 /// The contents of a sub-entry (such as `rom` or `disk`) that is a child of a ListInfo entry.
 ///
-/// Sub-entries can not contain their own sub-entries and can only contain scalars or lists of scalars.
-/// For example, the `rom` entries in the following fragment are sub-entries.
+/// Sub-entries contain scalars or lists of scalars, and may themselves contain
+/// further sub-entries, nested up to 64 levels deep when parsed. For example, the `rom`
+/// entries in the following fragment are sub-entries, as is the `status` group within `driver`.
 ///
 /// ```listinfo
 /// game (
 ///  rom (name "Rom 1")
 ///  rom (name "Rom 2")
+///  driver ( status ( emulation good ) )
 /// )
 /// ```
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SubEntry<'a> {
-    pub(crate) keys: IndexMap<&'a str, Node<&'a str>>,
+    pub(crate) keys: IndexMap<Cow<'a, str>, Node<EntryData<'a>>>,
 }
 
 impl<'a> SubEntry<'a> {
-    /// Retrieves the value of an item data value in the sub-entry.
-    pub fn value(&'a self, key: &str) -> Option<&'a Node<&'a str>> {
+    /// Gets the entry node with the given key if it exists.
+    pub fn entry(&'a self, key: &str) -> Option<&'a Node<EntryData<'a>>> {
         self.keys.get(key)
     }
 
+    /// Retrieves the value of an item data value in the sub-entry.
+    #[deprecated(
+        note = "sub-entries may nest, use `entry`, or `value_unique` for the first scalar"
+    )]
+    pub fn value(&'a self, key: &str) -> Option<&'a Node<EntryData<'a>>> {
+        self.entry(key)
+    }
+
     /// Gets the entry node with the given key if it exists.
     ///
-    /// This is shorthand for `subentry.value("key").map(|f| f.unique().as_ref())`
-    pub fn value_unique(&'a self, key: &str) -> Option<&'a str> {
-        self.keys.get(key).map(|f| *f.unique())
+    /// This is shorthand for `subentry.entry("key").and_then(|f| f.unique())`
+    pub fn entry_unique(&'a self, key: &str) -> Option<&'a EntryData<'a>> {
+        self.keys.get(key).and_then(|f| f.unique())
     }
 
     /// Gets the values with the given key if it exists.
     ///
-    /// This is shorthand for `fragment.value("key").map(|f| f.iter().map(|&s| s))`
+    /// This is shorthand for `subentry.entry("key").map(|f| f.iter())`
+    pub fn entry_iter(&'a self, key: &str) -> Option<impl Iterator<Item = &'a EntryData<'a>>> {
+        self.keys.get(key).map(|f| f.iter())
+    }
+
+    /// Gets the scalar value with the given key if it exists.
+    ///
+    /// Returns `None` if the first entry with the given key is a nested sub-entry.
+    pub fn value_unique(&'a self, key: &str) -> Option<&'a str> {
+        self.entry_unique(key).and_then(EntryData::as_scalar)
+    }
+
+    /// Gets the scalar values with the given key if it exists.
+    ///
+    /// Nested sub-entries with the given key are skipped.
     pub fn value_iter(&'a self, key: &str) -> Option<impl Iterator<Item = &'a str>> {
-        self.keys.get(key).map(|f| f.iter().copied())
+        self.entry_iter(key)
+            .map(|f| f.filter_map(EntryData::as_scalar))
     }
 
-    /// Gets a key value iterator over the values of this fragment.
-    pub fn iter(&'a self) -> EntryIter<'a, &'a str, Node<&'a str>> {
+    /// Gets a key value iterator over the values of this sub-entry.
+    pub fn iter(&'a self) -> EntryIter<'a, Cow<'a, str>, Node<EntryData<'a>>> {
         EntryIter::new(self.keys.iter())
     }
+
+    /// Copies any keys and values borrowed from the input, so that the sub-entry
+    /// no longer borrows from it.
+    pub fn into_owned(self) -> SubEntry<'static> {
+        SubEntry {
+            keys: entries_into_owned(self.keys),
+        }
+    }
 }
 
+/// Copies the keys and values of a fragment or sub-entry that are borrowed from the input.
+fn entries_into_owned(
+    keys: IndexMap<Cow<'_, str>, Node<EntryData<'_>>>,
+) -> IndexMap<Cow<'static, str>, Node<EntryData<'static>>> {
+    keys.into_iter()
+        .map(|(key, node)| {
+            (
+                Cow::Owned(key.into_owned()),
+                node.map(EntryData::into_owned),
+            )
+        })
+        .collect()
+}
+
+impl_entries_mut!(SubEntry);
+
 /// Represents an item data value of an entry.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum EntryData<'a> {
     /// A scalar string entry
-    Scalar(&'a str),
+    ///
+    /// Scalars are borrowed from the input unless they contained escape sequences
+    /// such as `\"` or `\\`, in which case the unescaped value is owned.
+    Scalar(Cow<'a, str>),
     /// A sub-entry (such as `rom`, for example)
     SubEntry(SubEntry<'a>),
 }
 
+impl<'a> EntryData<'a> {
+    /// Gets the value of the entry if it is a scalar.
+    pub fn as_scalar(&self) -> Option<&str> {
+        match self {
+            EntryData::Scalar(value) => Some(value),
+            EntryData::SubEntry(_) => None,
+        }
+    }
+
+    /// Copies the value or sub-entry if it is borrowed from the input.
+    pub fn into_owned(self) -> EntryData<'static> {
+        match self {
+            EntryData::Scalar(value) => EntryData::Scalar(Cow::Owned(value.into_owned())),
+            EntryData::SubEntry(sub_entry) => EntryData::SubEntry(sub_entry.into_owned()),
+        }
+    }
+
+    /// Gets the sub-entry if the entry is a sub-entry.
+    pub fn as_sub_entry(&self) -> Option<&SubEntry<'a>> {
+        match self {
+            EntryData::Scalar(_) => None,
+            EntryData::SubEntry(sub_entry) => Some(sub_entry),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for EntryData<'a> {
+    fn from(value: &'a str) -> Self {
+        EntryData::Scalar(Cow::Borrowed(value))
+    }
+}
+
+impl<'a> From<String> for EntryData<'a> {
+    fn from(value: String) -> Self {
+        EntryData::Scalar(Cow::Owned(value))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for EntryData<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        EntryData::Scalar(value)
+    }
+}
+
+impl<'a> From<SubEntry<'a>> for EntryData<'a> {
+    fn from(value: SubEntry<'a>) -> Self {
+        EntryData::SubEntry(value)
+    }
+}
+
 /// Represents nodes with the given key in an ListInfo entry.
 ///
 /// The split between `Unique` and `Many` is mostly for performance reasons
@@ -79,7 +386,7 @@ pub enum EntryData<'a> {
 ///
 /// Instead of accessing the enum members directly, the `Node::iter` and `Node::unique`
 /// methods abstract over the difference between `Unique` and `Many` for convenience.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Node<T> {
     /// A uniquely keyed node (only one of such key exists in the entry)
     Unique(T),
@@ -99,13 +406,122 @@ impl<'a, T> Node<T> {
     /// Gets a single value with the given key.
     ///
     /// If the provided key is not unique, retrieves the first
-    /// value of the many-set with the given key.
-    pub fn unique(&'a self) -> &T {
+    /// value of the many-set with the given key. Returns `None` if the node
+    /// is an empty many-set, which parsing never produces but a node built by hand may be.
+    pub fn unique(&'a self) -> Option<&'a T> {
+        self.as_slice().first()
+    }
+
+    /// Gets the number of values in the node.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns `true` if the node holds no values.
+    ///
+    /// Nodes built by this crate always hold at least one value.
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    /// Gets the values of the node as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            Node::Unique(entry) => core::slice::from_ref(entry),
+            Node::Many(entries) => entries,
+        }
+    }
+
+    /// Gets the values of the node as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match self {
+            Node::Unique(entry) => core::slice::from_mut(entry),
+            Node::Many(entries) => entries,
+        }
+    }
+
+    /// Gets the value at the given index.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Gets the value at the given index mutably.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(index)
+    }
+
+    /// Gets a mutable iterator over the values of the node.
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    /// Appends a value, turning a unique node into a many-set.
+    pub fn push(&mut self, value: T) {
+        *self = match core::mem::replace(self, Node::Many(Vec::new())) {
+            Node::Unique(first) => Node::Many(vec![first, value]),
+            Node::Many(mut values) => {
+                values.push(value);
+                Node::Many(values)
+            }
+        };
+    }
+
+    /// Removes the value at the given index.
+    ///
+    /// A node can not be left empty, so the last remaining value can not be removed;
+    /// remove its key from the fragment or sub-entry instead. If a single value
+    /// remains, the node becomes `Node::Unique`.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let values = match self {
+            Node::Many(values) if index < values.len() && values.len() > 1 => values,
+            _ => return None,
+        };
+        let value = values.remove(index);
+        if values.len() == 1 {
+            if let Some(last) = values.pop() {
+                *self = Node::Unique(last);
+            }
+        }
+        Some(value)
+    }
+
+    /// Creates a node from the given values, or `None` if there are no values.
+    ///
+    /// A single value becomes `Node::Unique`.
+    pub fn from_vec(mut values: Vec<T>) -> Option<Self> {
+        match values.len() {
+            0 => None,
+            1 => values.pop().map(Node::Unique),
+            _ => Some(Node::Many(values)),
+        }
+    }
+
+    /// Converts the node into a vector of its values.
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Node::Unique(entry) => vec![entry],
+            Node::Many(entries) => entries,
+        }
+    }
+
+    /// Keeps only the values for which the predicate returns `true`,
+    /// or returns `None` if no values are left.
+    pub(crate) fn retain<F: FnMut(&T) -> bool>(self, mut f: F) -> Option<Self> {
+        match self {
+            Node::Unique(entry) if f(&entry) => Some(Node::Unique(entry)),
+            Node::Unique(_) => None,
+            Node::Many(mut entries) => {
+                entries.retain(f);
+                Node::from_vec(entries)
+            }
+        }
+    }
+
+    /// Maps each value of the node, preserving whether the node is unique.
+    pub(crate) fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Node<U> {
         match self {
-            Node::Unique(entry) => entry,
-            // Node::Many must have vec of arity 2 or more
-            // Any other situation is a bug, and should panic.
-            Node::Many(entries) => entries.first().unwrap(),
+            Node::Unique(value) => Node::Unique(f(value)),
+            Node::Many(values) => Node::Many(values.into_iter().map(f).collect()),
         }
     }
 }
@@ -121,17 +537,31 @@ impl<'a, T> Node<T> {
 ///   name "DAT file."
 /// )
 /// ```
-#[derive(Debug)]
+///
+/// Fragments can also be built and edited programmatically.
+///
+/// ```rust
+/// use listinfo::{EntryFragment, SubEntry};
+/// use listinfo::write::WriteOptions;
+///
+/// let mut game = EntryFragment::new()
+///     .with("name", "Cave Story (En)")
+///     .with("rom", SubEntry::new().with("name", "Doukutsu.exe"))
+///     .with("rom", SubEntry::new().with("name", "data.pak"));
+///
+/// game.retain(|key, _| key != "rom");
+/// game.insert("description", String::from("Cave Story"));
+///
+/// let mut dat = String::new();
+/// game.write_to("game", &mut dat, &WriteOptions::default()).unwrap();
+/// assert_eq!(dat, "game (\n\tname \"Cave Story (En)\"\n\tdescription \"Cave Story\"\n)\n");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct EntryFragment<'a> {
-    keys: IndexMap<&'a str, Node<EntryData<'a>>>,
+    pub(crate) keys: IndexMap<Cow<'a, str>, Node<EntryData<'a>>>,
 }
 
 impl<'a> EntryFragment<'a> {
-    #[doc(hidden)]
-    pub(crate) fn new(keys: IndexMap<&'a str, Node<EntryData<'a>>>) -> Self {
-        EntryFragment { keys }
-    }
-
     /// Gets the entry node with the given key if it exists.
     pub fn entry(&'a self, key: &str) -> Option<&'a Node<EntryData<'a>>> {
         self.keys.get(key)
@@ -139,20 +569,30 @@ impl<'a> EntryFragment<'a> {
 
     /// Gets the entry node with the given key if it exists.
     ///
-    /// This is shorthand for `fragment.entry("key").map(|f| f.unique())`
-    pub fn entry_unique(&'a self, key: &str) -> Option<&'a EntryData> {
-        self.keys.get(key).map(|f| f.unique())
+    /// This is shorthand for `fragment.entry("key").and_then(|f| f.unique())`
+    pub fn entry_unique(&'a self, key: &str) -> Option<&'a EntryData<'a>> {
+        self.keys.get(key).and_then(|f| f.unique())
     }
 
     /// Gets the values with the given key if it exists.
     ///
     /// This is shorthand for `fragment.entry("key").map(|f| f.iter())`
-    pub fn entry_iter(&'a self, key: &str) -> Option<impl Iterator<Item = &'a EntryData>> {
+    pub fn entry_iter(&'a self, key: &str) -> Option<impl Iterator<Item = &'a EntryData<'a>>> {
         self.keys.get(key).map(|f| f.iter())
     }
 
     /// Gets an key value iterator over the entries of this fragment.
-    pub fn iter(&'a self) -> EntryIter<'a, &'a str, Node<EntryData<'a>>> {
+    pub fn iter(&'a self) -> EntryIter<'a, Cow<'a, str>, Node<EntryData<'a>>> {
         EntryIter::new(self.keys.iter())
     }
+
+    /// Copies any keys and values borrowed from the input, so that the fragment
+    /// no longer borrows from it.
+    pub fn into_owned(self) -> EntryFragment<'static> {
+        EntryFragment {
+            keys: entries_into_owned(self.keys),
+        }
+    }
 }
+
+impl_entries_mut!(EntryFragment);
@@ -1,7 +1,10 @@
-use nom::Err;
-
-use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+#[cfg(feature = "deserialize")]
+use alloc::format;
 use alloc::string::String;
+#[cfg(any(feature = "deserialize", feature = "serialize"))]
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 use core::fmt::{self, Display, Formatter};
 
@@ -9,9 +12,46 @@ use core::fmt::{self, Display, Formatter};
 #[derive(Debug)]
 pub enum Error {
     /// Error returned by the parser when parsing fails.
-    ParseError(String),
+    ParseError(Box<ParseError>),
     /// Error returned by serde.
     SerdeError(String),
+    /// A value could not be deserialized as the type expected at its path.
+    InvalidType {
+        /// The path of the value.
+        path: KeyPath,
+        /// A description of the value and the expected type.
+        message: String,
+    },
+    /// A value was not valid for the type expected at its path.
+    ///
+    /// This is also returned for errors raised by `Deserialize` implementations.
+    InvalidValue {
+        /// The path of the value.
+        path: KeyPath,
+        /// A description of the value and the expected values.
+        message: String,
+    },
+    /// A field required by the deserialized type was missing from the fragment or sub-entry at the path.
+    MissingField {
+        /// The path of the fragment or sub-entry.
+        path: KeyPath,
+        /// The name of the missing field.
+        field: &'static str,
+    },
+    /// A sub-entry was found at the path where a single value was expected.
+    UnexpectedNesting {
+        /// The path of the sub-entry.
+        path: KeyPath,
+        /// A description of the expected value.
+        expected: String,
+    },
+    /// Error returned when converting parsed elements into the typed model.
+    ModelError(String),
+    /// Error returned when reading an XML DAT fails.
+    XmlError(String),
+    /// Error returned by the underlying IO stream.
+    #[cfg(feature = "std")]
+    IoError(alloc::io::Error),
     /// Unknown or unexpected error occurred.
     UnknownError,
 }
@@ -19,29 +59,394 @@ pub enum Error {
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Error::ParseError(msg) => f.write_str(msg),
+            Error::ParseError(err) => err.fmt(f),
             Error::SerdeError(msg) => f.write_str(msg),
+            Error::InvalidType { path, message } | Error::InvalidValue { path, message } => {
+                write_path(f, path)?;
+                f.write_str(message)
+            }
+            Error::MissingField { path, field } => {
+                write_path(f, path)?;
+                write!(f, "missing field `{}`", field)
+            }
+            Error::UnexpectedNesting { path, expected } => {
+                write_path(f, path)?;
+                write!(f, "expected {}, found a sub-entry", expected)
+            }
+            Error::ModelError(msg) => f.write_str(msg),
+            Error::XmlError(msg) => f.write_str(msg),
+            #[cfg(feature = "std")]
+            Error::IoError(err) => err.fmt(f),
             Error::UnknownError => f.write_str("Unknown Error"),
         }
     }
 }
 
+fn write_path(f: &mut Formatter<'_>, path: &KeyPath) -> fmt::Result {
+    if path.is_empty() {
+        Ok(())
+    } else {
+        write!(f, "{}: ", path)
+    }
+}
+
+impl Error {
+    /// Gets the path of the value a deserialization error occurred at.
+    ///
+    /// Returns `None` for errors that are not about a value, such as parse errors.
+    pub fn path(&self) -> Option<&KeyPath> {
+        match self {
+            Error::InvalidType { path, .. }
+            | Error::InvalidValue { path, .. }
+            | Error::MissingField { path, .. }
+            | Error::UnexpectedNesting { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Gets the path of the value the error occurred at, if the error has one.
+    #[cfg(feature = "deserialize")]
+    fn path_mut(&mut self) -> Option<&mut KeyPath> {
+        match self {
+            Error::InvalidType { path, .. }
+            | Error::InvalidValue { path, .. }
+            | Error::MissingField { path, .. }
+            | Error::UnexpectedNesting { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Adds the key of the fragment or sub-entry that contains the value to the front of the path.
+    #[cfg(feature = "deserialize")]
+    pub(crate) fn at_key(mut self, key: &str) -> Self {
+        if let Some(path) = self.path_mut() {
+            path.segments.insert(0, PathSegment::Key(key.to_string()));
+        }
+        self
+    }
+
+    /// Adds the index of the value within the values of its key to the front of the path.
+    #[cfg(feature = "deserialize")]
+    pub(crate) fn at_index(mut self, index: usize) -> Self {
+        if let Some(path) = self.path_mut() {
+            path.segments.insert(0, PathSegment::Index(index));
+        }
+        self
+    }
+
+    /// Records the `name` of the fragment that contains the value, unless one is recorded already.
+    #[cfg(feature = "deserialize")]
+    pub(crate) fn in_fragment(mut self, name: Option<&str>) -> Self {
+        if let Some(path) = self.path_mut() {
+            if path.fragment_name.is_none() {
+                path.fragment_name = name.map(String::from);
+            }
+        }
+        self
+    }
+
+    /// Creates an error for a sub-entry that was found where a single value was expected.
+    #[cfg(feature = "deserialize")]
+    pub(crate) fn unexpected_nesting(expected: &dyn serde::de::Expected) -> Self {
+        Error::UnexpectedNesting {
+            path: KeyPath::default(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 impl alloc::error::Error for Error {}
 
 #[cfg(feature = "deserialize")]
 impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::InvalidValue {
+            path: KeyPath::default(),
+            message: msg.to_string(),
+        }
+    }
+
+    fn invalid_type(
+        unexpected: serde::de::Unexpected<'_>,
+        expected: &dyn serde::de::Expected,
+    ) -> Self {
+        Error::InvalidType {
+            path: KeyPath::default(),
+            message: format!("invalid type: {}, expected {}", unexpected, expected),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField {
+            path: KeyPath::default(),
+            field,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
         Error::SerdeError(msg.to_string())
     }
 }
 
-impl From<Err<nom::error::Error<&str>>> for Error {
-    fn from(err: Err<nom::error::Error<&str>>) -> Self {
-        match err {
-            Err::Incomplete(_) => Error::UnknownError,
-            Err::Error(e) => Error::ParseError(e.code.description().to_owned()),
-            Err::Failure(e) => Error::ParseError(e.code.description().to_owned()),
+#[cfg(feature = "std")]
+impl From<alloc::io::Error> for Error {
+    fn from(err: alloc::io::Error) -> Self {
+        Error::IoError(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::ParseError(Box::new(err))
+    }
+}
+
+/// The path of keys and indices to a value within a document, such as `game[12].rom[3].size`.
+///
+/// An index is only part of the path when the value was deserialized from a sequence of values.
+/// When the value is within a fragment that has a `name`, the name is kept with the path,
+/// and the path is displayed as `game[12] (name "Foo (USA)") → rom[3].size`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct KeyPath {
+    segments: Vec<PathSegment>,
+    fragment_name: Option<String>,
+}
+
+/// A key or index within a [`KeyPath`](struct.KeyPath.html).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PathSegment {
+    /// The key of a fragment, value or sub-entry.
+    Key(String),
+    /// The index of a value among the values with the same key.
+    Index(usize),
+}
+
+impl KeyPath {
+    /// Gets the keys and indices of the path, from the outermost to the innermost.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Gets the `name` of the fragment that contains the value, if it has one.
+    pub fn fragment_name(&self) -> Option<&str> {
+        self.fragment_name.as_deref()
+    }
+
+    /// Whether the path has no keys or indices.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+/// Writes path segments, separating keys with dots.
+fn write_segments(f: &mut Formatter<'_>, segments: &[PathSegment], mut dot: bool) -> fmt::Result {
+    for segment in segments {
+        match segment {
+            PathSegment::Key(key) if dot => write!(f, ".{}", key)?,
+            PathSegment::Key(key) => f.write_str(key)?,
+            PathSegment::Index(index) => write!(f, "[{}]", index)?,
+        }
+        dot = true;
+    }
+    Ok(())
+}
+
+impl Display for KeyPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // The fragment is the key of the document and the index of the fragment, if any.
+        let fragment_len = match self.segments.get(1) {
+            Some(PathSegment::Index(_)) => 2,
+            _ => 1,
+        };
+        let (fragment, rest) = self
+            .segments
+            .split_at(fragment_len.min(self.segments.len()));
+        write_segments(f, fragment, false)?;
+        match &self.fragment_name {
+            Some(name) => {
+                write!(f, " (name \"{}\")", name)?;
+                if !rest.is_empty() {
+                    f.write_str(" → ")?;
+                    write_segments(f, rest, false)?;
+                }
+                Ok(())
+            }
+            None => write_segments(f, rest, true),
+        }
+    }
+}
+
+/// Error returned when parsing a hex encoded hash or checksum fails.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParseHashError {
+    /// The hex string did not have the expected number of digits.
+    InvalidLength {
+        /// The number of hex digits expected.
+        expected: usize,
+        /// The length of the hex string.
+        found: usize,
+    },
+    /// The hex string contained a character that is not a hex digit.
+    InvalidCharacter {
+        /// The offending character.
+        character: char,
+        /// The byte index of the offending character.
+        index: usize,
+    },
+}
+
+impl Display for ParseHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseHashError::InvalidLength { expected, found } => write!(
+                f,
+                "expected {} hex digits, found a string of length {}",
+                expected, found
+            ),
+            ParseHashError::InvalidCharacter { character, index } => {
+                write!(f, "invalid hex digit {:?} at index {}", character, index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl alloc::error::Error for ParseHashError {}
+
+/// The maximum number of characters of the offending line shown on either side of the error.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Describes where and why parsing a ListInfo DAT failed.
+///
+/// The `Display` implementation renders the offending line of input
+/// with a caret pointing at the position of the error.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    offset: usize,
+    line: usize,
+    column: usize,
+    fragment: Option<String>,
+    name: Option<String>,
+    expected: String,
+    snippet: String,
+    snippet_column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(
+        input: &str,
+        offset: usize,
+        expected: String,
+        fragment: Option<&str>,
+        name: Option<&str>,
+    ) -> Self {
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        let column = input[line_start..offset].chars().count() + 1;
+
+        // Only keep a window around the error so huge single-line inputs stay readable.
+        let text = input[line_start..line_end].trim_end_matches('\r');
+        let skip = (column - 1).saturating_sub(SNIPPET_CONTEXT);
+        let mut snippet: String = text.chars().skip(skip).take(SNIPPET_CONTEXT * 2).collect();
+        let mut snippet_column = column - skip;
+        if skip > 0 {
+            snippet.insert_str(0, "...");
+            snippet_column += 3;
+        }
+        if text.chars().count() > skip + SNIPPET_CONTEXT * 2 {
+            snippet.push_str("...");
+        }
+
+        ParseError {
+            offset,
+            line,
+            column,
+            fragment: fragment.map(String::from),
+            name: name.map(String::from),
+            expected,
+            snippet,
+            snippet_column,
+        }
+    }
+
+    /// Moves an error in a chunk of input to its position in the complete input,
+    /// given the bytes, lines and characters of the current line that preceded the chunk.
+    #[cfg(feature = "std")]
+    pub(crate) fn relocate(&mut self, offset: usize, lines: usize, column: usize) {
+        if self.line == 1 {
+            self.column += column;
+        }
+        self.offset += offset;
+        self.line += lines;
+    }
+
+    /// The byte offset into the input where parsing failed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The 1-based line number where parsing failed.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column, in characters, where parsing failed.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The key of the fragment that was being parsed, such as `game`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+
+    /// The value of the nearest `name` of the fragment that was being parsed.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// A description of the token that was expected at the position of the error.
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// The offending line of input.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} at line {}, column {}",
+            self.expected, self.line, self.column
+        )?;
+        match (&self.fragment, &self.name) {
+            (Some(fragment), Some(name)) => write!(f, " in {} (name \"{}\")", fragment, name)?,
+            (Some(fragment), None) => write!(f, " in {}", fragment)?,
+            _ => {}
+        }
+
+        let gutter =
+            core::iter::successors(Some(self.line), |n| Some(n / 10).filter(|&n| n > 0)).count();
+        writeln!(f)?;
+        writeln!(f, "{:gutter$} |", "", gutter = gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{:gutter$} | ", "", gutter = gutter)?;
+        // Keep tabs in the caret line so the caret lines up with the snippet.
+        for c in self.snippet.chars().take(self.snippet_column - 1) {
+            f.write_str(if c == '\t' { "\t" } else { " " })?;
         }
+        f.write_str("^")
     }
 }
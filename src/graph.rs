@@ -0,0 +1,279 @@
+//! Parent, clone and BIOS relationships between the sets of a document.
+//!
+//! MAME style DATs encode inheritance between sets with the `cloneof` and `romof` values of
+//! their `game`, `machine` and `resource` fragments. A [`SetGraph`](struct.SetGraph.html)
+//! resolves these references by set name, and reports references to sets that are not in the
+//! document and sets that inherit from each other in a loop.
+//! [`DatDocument::rebuild`](../struct.DatDocument.html#method.rebuild) uses these relationships
+//! to rebuild a document into non-merged, split or merged sets.
+//!
+//! ```rust
+//! use listinfo::parse;
+//! use listinfo::graph::SetGraph;
+//!
+//! const DAT: &str = r#"resource ( name neogeo )
+//!     game ( name mslug romof neogeo )
+//!     game ( name mslugj cloneof mslug romof mslug )"#;
+//!
+//! let document = parse::parse_document(DAT).unwrap();
+//! let graph = SetGraph::new(&document);
+//! assert_eq!(graph.parent_of("mslugj"), Some("mslug"));
+//! assert_eq!(graph.clones_of("mslug"), ["mslugj"]);
+//! assert_eq!(graph.bios_of("mslugj"), Some("neogeo"));
+//! assert!(graph.issues().is_empty());
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use indexmap::IndexMap;
+
+use crate::elements::*;
+
+mod rebuild;
+
+pub use rebuild::Layout;
+
+/// The fragment keys of the sets in a document.
+const SET_KEYS: [&str; 3] = ["game", "machine", "resource"];
+
+/// A problem with the references between the sets of a document.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GraphIssue<'a> {
+    /// A set with the same name as an earlier set, which is ignored by the graph.
+    DuplicateSet(&'a str),
+    /// A `cloneof` or `romof` reference to a set that is not in the document.
+    DanglingReference {
+        /// The name of the referencing set.
+        set: &'a str,
+        /// The key of the reference, either `cloneof` or `romof`.
+        key: &'static str,
+        /// The name of the missing set.
+        target: &'a str,
+    },
+    /// Sets that inherit from each other in a loop, in the order of their references.
+    Cycle(Vec<&'a str>),
+}
+
+impl<'a> Display for GraphIssue<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphIssue::DuplicateSet(name) => write!(f, "duplicate set \"{}\"", name),
+            GraphIssue::DanglingReference { set, key, target } => write!(
+                f,
+                "set \"{}\" has {} \"{}\", which is not in the document",
+                set, key, target
+            ),
+            GraphIssue::Cycle(sets) => {
+                f.write_str("sets inherit from each other in a loop: ")?;
+                for set in sets {
+                    write!(f, "\"{}\" -> ", set)?;
+                }
+                match sets.first() {
+                    Some(first) => write!(f, "\"{}\"", first),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+struct Set<'a> {
+    fragment: &'a EntryFragment<'a>,
+    cloneof: Option<&'a str>,
+    romof: Option<&'a str>,
+    clones: Vec<&'a str>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// The parent, clone and BIOS relationships between the sets of a document.
+///
+/// Sets are the `game`, `machine` and `resource` fragments of the document, identified
+/// by their `name`. Fragments without a name are ignored.
+pub struct SetGraph<'a> {
+    sets: IndexMap<&'a str, Set<'a>>,
+    issues: Vec<GraphIssue<'a>>,
+}
+
+impl<'a> SetGraph<'a> {
+    /// Builds the graph of the sets in the document.
+    pub fn new(document: &'a DatDocument<'a>) -> Self {
+        let mut graph = SetGraph {
+            sets: IndexMap::new(),
+            issues: Vec::new(),
+        };
+
+        for (key, fragments) in document.iter() {
+            if !SET_KEYS.contains(&key) {
+                continue;
+            }
+            for fragment in fragments {
+                let name = match fragment.entry_unique("name").and_then(EntryData::as_scalar) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                if graph.sets.contains_key(name) {
+                    graph.issues.push(GraphIssue::DuplicateSet(name));
+                    continue;
+                }
+                let scalar = |key| fragment.entry_unique(key).and_then(EntryData::as_scalar);
+                graph.sets.insert(
+                    name,
+                    Set {
+                        fragment,
+                        cloneof: scalar("cloneof"),
+                        romof: scalar("romof"),
+                        clones: Vec::new(),
+                    },
+                );
+            }
+        }
+
+        for index in 0..graph.sets.len() {
+            let (&name, set) = match graph.sets.get_index(index) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let references = [("cloneof", set.cloneof), ("romof", set.romof)];
+            for (key, target) in references.iter() {
+                if let Some(target) = *target {
+                    if !graph.sets.contains_key(target) {
+                        graph.issues.push(GraphIssue::DanglingReference {
+                            set: name,
+                            key,
+                            target,
+                        });
+                    }
+                }
+            }
+            if let Some(parent) = set.cloneof.and_then(|parent| graph.sets.get_mut(parent)) {
+                parent.clones.push(name);
+            }
+        }
+
+        graph.find_cycles();
+        graph
+    }
+
+    /// Gets the indices of the sets referenced by the set at the given index.
+    fn targets(&self, index: usize) -> [Option<usize>; 2] {
+        let set = match self.sets.get_index(index) {
+            Some((_, set)) => set,
+            None => return [None, None],
+        };
+        let resolve = |name: Option<&str>| name.and_then(|name| self.sets.get_full(name));
+        let cloneof = resolve(set.cloneof).map(|(index, _, _)| index);
+        let romof = resolve(set.romof)
+            .map(|(index, _, _)| index)
+            .filter(|&index| Some(index) != cloneof);
+        [cloneof, romof]
+    }
+
+    /// Reports every loop of references with a depth-first search over all sets.
+    fn find_cycles(&mut self) {
+        let mut visits = vec![Visit::New; self.sets.len()];
+        for start in 0..self.sets.len() {
+            if visits[start] != Visit::New {
+                continue;
+            }
+            visits[start] = Visit::Active;
+            let mut stack = vec![(start, 0)];
+            while let Some((index, edge)) = stack.last_mut() {
+                let index = *index;
+                let target = match self.targets(index).get(*edge) {
+                    Some(target) => *target,
+                    None => {
+                        visits[index] = Visit::Done;
+                        stack.pop();
+                        continue;
+                    }
+                };
+                *edge += 1;
+                let target = match target {
+                    Some(target) => target,
+                    None => continue,
+                };
+                match visits[target] {
+                    Visit::New => {
+                        visits[target] = Visit::Active;
+                        stack.push((target, 0));
+                    }
+                    Visit::Active => {
+                        let start = stack.iter().position(|&(index, _)| index == target);
+                        let cycle = stack[start.unwrap_or(0)..]
+                            .iter()
+                            .filter_map(|&(index, _)| self.sets.get_index(index))
+                            .map(|(&name, _)| name)
+                            .collect();
+                        self.issues.push(GraphIssue::Cycle(cycle));
+                    }
+                    Visit::Done => {}
+                }
+            }
+        }
+    }
+
+    /// Gets the problems found with the references between sets.
+    pub fn issues(&self) -> &[GraphIssue<'a>] {
+        &self.issues
+    }
+
+    /// Gets the names of the sets in the graph, in document order.
+    pub fn sets(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.sets.keys().copied()
+    }
+
+    /// Gets the fragment of the set with the given name.
+    pub fn get(&self, name: &str) -> Option<&'a EntryFragment<'a>> {
+        self.sets.get(name).map(|set| set.fragment)
+    }
+
+    /// Gets the `cloneof` parent of the set with the given name.
+    ///
+    /// The parent is returned as written, even if it is not in the document.
+    pub fn parent_of(&self, name: &str) -> Option<&'a str> {
+        self.sets.get(name).and_then(|set| set.cloneof)
+    }
+
+    /// Gets the `romof` set of the set with the given name, which it shares ROMs with.
+    ///
+    /// This is the parent of a clone, or the BIOS of a parent set.
+    pub fn romof(&self, name: &str) -> Option<&'a str> {
+        self.sets.get(name).and_then(|set| set.romof)
+    }
+
+    /// Gets the clones of the set with the given name, in document order.
+    pub fn clones_of(&self, name: &str) -> &[&'a str] {
+        self.sets
+            .get(name)
+            .map(|set| set.clones.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Gets the BIOS set that the set with the given name depends on.
+    ///
+    /// This is the `romof` of the topmost parent of the set, found by following `cloneof`.
+    /// Returns `None` if the set is not in the graph, or if the set and its parents
+    /// inherit from each other in a loop.
+    pub fn bios_of(&self, name: &str) -> Option<&'a str> {
+        let (_, &name, mut set) = self.sets.get_full(name)?;
+        let mut parents = vec![name];
+        while let Some((_, &parent, parent_set)) =
+            set.cloneof.and_then(|parent| self.sets.get_full(parent))
+        {
+            if parents.contains(&parent) {
+                return None;
+            }
+            parents.push(parent);
+            set = parent_set;
+        }
+        set.romof
+            .filter(|&romof| Some(romof) != set.cloneof && !parents.contains(&romof))
+    }
+}
@@ -0,0 +1,459 @@
+//! Rebuilding documents into non-merged, split and merged sets.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use indexmap::{IndexMap, IndexSet};
+
+use super::{SetGraph, SET_KEYS};
+use crate::elements::*;
+
+/// The keys of the sub-entries that describe the files of a set.
+const FILE_KEYS: [&str; 2] = ["rom", "disk"];
+
+/// How the ROMs shared between parents, clones, BIOS sets and devices are stored.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Layout {
+    /// Every set contains all of the ROMs it needs, including those of its parent,
+    /// its BIOS set and its devices.
+    NonMerged,
+    /// Parents contain the ROMs that are not in their BIOS set or devices, and clones
+    /// only contain the ROMs that differ from their parent.
+    Split,
+    /// Clones are merged into their parent, which contains the ROMs of the parent and every
+    /// clone that are not in their BIOS set or devices. A clone ROM with the name of a
+    /// different ROM of the parent is stored as `clone/name`, in a directory named after
+    /// the clone.
+    Merged,
+}
+
+impl Layout {
+    /// The `forcemerging` value of the layout in a `clrmamepro` header.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Layout::NonMerged => "none",
+            Layout::Split => "split",
+            Layout::Merged => "full",
+        }
+    }
+}
+
+impl<'a> DatDocument<'a> {
+    /// Rebuilds the sets of the document into the given layout.
+    ///
+    /// The document is expected to list every ROM that a set needs, with the `merge`
+    /// value of a ROM naming the ROM of its `romof` set that it is shared with, as MAME
+    /// writes them. ROMs of the sets named by `device_ref` are only added to sets in the
+    /// non-merged layout. ROMs and disks are compared by name and any hashes they both have.
+    ///
+    /// A set without any `merge` values, as in a split document, is taken to list only its
+    /// own ROMs. In the non-merged layout, it gets the ROMs of its parent and BIOS set that it
+    /// does not have a ROM of the same name for, marked with a `merge` value.
+    ///
+    /// Fragments that are not sets are copied as is, except that the `forcemerging` value of
+    /// the `clrmamepro` header is set to match the layout.
+    ///
+    /// ```rust
+    /// use listinfo::parse;
+    /// use listinfo::graph::Layout;
+    ///
+    /// const DAT: &str = r#"game ( name pacman rom ( name pacman.6e size 4096 ) )
+    ///     game ( name puckman cloneof pacman romof pacman
+    ///         rom ( name pacman.6e merge pacman.6e size 4096 )
+    ///         rom ( name puckman.6f size 4096 )
+    ///     )"#;
+    ///
+    /// let document = parse::parse_document(DAT).unwrap();
+    /// let merged = document.rebuild(Layout::Merged);
+    /// assert_eq!(merged.entry("game").unwrap().count(), 1);
+    /// ```
+    pub fn rebuild(&self, layout: Layout) -> DatDocument<'a> {
+        let rebuild = Rebuild::new(self);
+        let mut document = DatDocument::new();
+        for (key, fragments) in self.document.iter() {
+            for fragment in fragments {
+                let name = match rebuild.name_of(key, fragment) {
+                    Some(name) => name,
+                    None => {
+                        document.push(key.clone(), fragment.clone());
+                        continue;
+                    }
+                };
+                let fragment = match layout {
+                    Layout::NonMerged => rebuild.non_merged(name, fragment),
+                    Layout::Split => rebuild.split(name, fragment),
+                    Layout::Merged => match rebuild.members.get(name) {
+                        Some(members) => rebuild.merged(name, fragment, members),
+                        None => continue,
+                    },
+                };
+                document.push(key.clone(), fragment);
+            }
+        }
+        if let Some(headers) = document.entry_mut("clrmamepro") {
+            for header in headers {
+                header.insert("forcemerging", layout.as_str());
+            }
+        }
+        document
+    }
+}
+
+/// A set of the document, with its ROMs and disks indexed by name.
+struct Set<'s, 'a> {
+    fragment: &'s EntryFragment<'a>,
+    files: FileIndex<'s>,
+}
+
+struct Rebuild<'s, 'a> {
+    graph: SetGraph<'s>,
+    sets: IndexMap<&'s str, Set<'s, 'a>>,
+    /// The clones to merge into each set that is not merged into a parent.
+    members: IndexMap<&'s str, Vec<&'s str>>,
+}
+
+impl<'s, 'a> Rebuild<'s, 'a> {
+    fn new(document: &'s DatDocument<'a>) -> Self {
+        let mut sets = IndexMap::new();
+        for (key, fragments) in document.document.iter() {
+            if !SET_KEYS.contains(&&**key) {
+                continue;
+            }
+            for fragment in fragments {
+                if let Some(name) = value(fragment, "name") {
+                    sets.entry(name).or_insert_with(|| Set {
+                        fragment,
+                        files: FileIndex::new(fragment),
+                    });
+                }
+            }
+        }
+
+        let mut rebuild = Rebuild {
+            graph: SetGraph::new(document),
+            sets,
+            members: IndexMap::new(),
+        };
+        let roots: Vec<_> = rebuild
+            .sets
+            .keys()
+            .map(|&name| (name, rebuild.root_of(name)))
+            .collect();
+        for (name, root) in roots {
+            let members = rebuild.members.entry(root).or_default();
+            if name != root {
+                members.push(name);
+            }
+        }
+        rebuild
+    }
+
+    /// Gets the name of a fragment if it is a set of the graph.
+    ///
+    /// Later fragments with the name of an earlier set are not sets of the graph.
+    fn name_of(&self, key: &str, fragment: &EntryFragment<'a>) -> Option<&'s str> {
+        let (_, &name, set) = self.sets.get_full(value(fragment, "name")?)?;
+        if SET_KEYS.contains(&key) && core::ptr::eq(set.fragment, fragment) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the topmost parent of a set that is in the document, or the set itself if its
+    /// parents inherit from each other in a loop.
+    fn root_of(&self, name: &'s str) -> &'s str {
+        let mut parents = vec![name];
+        let mut root = name;
+        while let Some(parent) = self
+            .graph
+            .parent_of(root)
+            .filter(|parent| self.sets.contains_key(parent))
+        {
+            if parents.contains(&parent) {
+                return name;
+            }
+            parents.push(parent);
+            root = parent;
+        }
+        root
+    }
+
+    /// Gets the sets named by the `device_ref` values of a set and its devices.
+    fn devices_of(&self, fragment: &'s EntryFragment<'a>) -> Vec<&Set<'s, 'a>> {
+        let mut devices = IndexSet::new();
+        let mut pending = vec![fragment];
+        while let Some(fragment) = pending.pop() {
+            for device in values(fragment, "device_ref") {
+                let name = match device {
+                    EntryData::Scalar(name) => Some(&**name),
+                    EntryData::SubEntry(device) => sub_value(device, "name"),
+                };
+                if let Some((index, _, device)) = name.and_then(|name| self.sets.get_full(name)) {
+                    if devices.insert(index) {
+                        pending.push(device.fragment);
+                    }
+                }
+            }
+        }
+        devices
+            .into_iter()
+            .filter_map(|index| self.sets.get_index(index))
+            .map(|(_, device)| device)
+            .collect()
+    }
+
+    /// Gets the sets that a set shares ROMs with, nearest first, by following `romof`,
+    /// or `cloneof` for a clone without a `romof` value.
+    fn romof_chain(&self, name: &'s str) -> Vec<&Set<'s, 'a>> {
+        let mut names = vec![name];
+        let mut chain = Vec::new();
+        while let Some((&romof, set)) = names
+            .last()
+            .and_then(|&name| {
+                self.graph
+                    .romof(name)
+                    .or_else(|| self.graph.parent_of(name))
+            })
+            .and_then(|romof| self.sets.get_key_value(romof))
+        {
+            if names.contains(&romof) {
+                break;
+            }
+            names.push(romof);
+            chain.push(set);
+        }
+        chain
+    }
+
+    fn non_merged(&self, name: &'s str, fragment: &'s EntryFragment<'a>) -> EntryFragment<'a> {
+        let mut rebuilt = fragment.clone();
+        let mut files = FileIndex::new(fragment);
+        let lists_shared = FILE_KEYS
+            .iter()
+            .flat_map(|key| values(fragment, key))
+            .filter_map(EntryData::as_sub_entry)
+            .any(|file| sub_value(file, "merge").is_some());
+        if !lists_shared {
+            for romof in self.romof_chain(name) {
+                for &key in FILE_KEYS.iter() {
+                    for file in values(romof.fragment, key) {
+                        let file_name = match name_of(file) {
+                            Some(file_name) => file_name,
+                            None => continue,
+                        };
+                        if files.has_name(key, file_name) {
+                            continue;
+                        }
+                        let mut file = file.clone();
+                        if let EntryData::SubEntry(sub_entry) = &mut file {
+                            sub_entry.insert("merge", String::from(file_name));
+                        }
+                        files.push(&mut rebuilt, key, file, Some(Cow::Borrowed(file_name)));
+                    }
+                }
+            }
+        }
+        for device in self.devices_of(fragment) {
+            for &key in FILE_KEYS.iter() {
+                for file in values(device.fragment, key) {
+                    if !files.contains(&rebuilt, key, file) {
+                        let name = name_of(file).map(Cow::Borrowed);
+                        files.push(&mut rebuilt, key, file.clone(), name);
+                    }
+                }
+            }
+        }
+        rebuilt
+    }
+
+    fn split(&self, name: &str, fragment: &'s EntryFragment<'a>) -> EntryFragment<'a> {
+        let romof = self
+            .graph
+            .romof(name)
+            .or_else(|| self.graph.parent_of(name))
+            .and_then(|romof| self.sets.get(romof));
+        let devices = self.devices_of(fragment);
+        let mut rebuilt = fragment.clone();
+        rebuilt.retain(|key, file| {
+            let sub_entry = match file {
+                EntryData::SubEntry(sub_entry) if FILE_KEYS.contains(&key) => sub_entry,
+                _ => return true,
+            };
+            let merged = match (sub_value(sub_entry, "merge"), romof) {
+                (Some(merge), Some(romof)) => romof.files.has_name(key, merge),
+                _ => false,
+            };
+            let in_device = devices
+                .iter()
+                .any(|device| device.files.contains(device.fragment, key, file));
+            !merged && !in_device
+        });
+        rebuilt
+    }
+
+    fn merged(
+        &self,
+        name: &str,
+        fragment: &'s EntryFragment<'a>,
+        members: &[&'s str],
+    ) -> EntryFragment<'a> {
+        let mut rebuilt = self.split(name, fragment);
+        let mut files = FileIndex::new(&rebuilt).into_owned();
+        for &clone in members {
+            let mut split = match self.sets.get(clone) {
+                Some(set) => self.split(clone, set.fragment),
+                None => continue,
+            };
+            for &key in FILE_KEYS.iter() {
+                let clone_files = split.remove(key).map(Node::into_vec).unwrap_or_default();
+                for mut file in clone_files {
+                    if files.contains(&rebuilt, key, &file) {
+                        continue;
+                    }
+                    // A clone's own version of a file is kept in a directory named after the clone.
+                    if let EntryData::SubEntry(sub_entry) = &mut file {
+                        let renamed = sub_value(sub_entry, "name")
+                            .filter(|&name| files.has_name(key, name))
+                            .map(|name| format!("{}/{}", clone, name));
+                        if let Some(renamed) = renamed {
+                            sub_entry.insert("name", renamed);
+                        }
+                    }
+                    let name = name_of(&file).map(|name| Cow::Owned(String::from(name)));
+                    files.push(&mut rebuilt, key, file, name);
+                }
+            }
+        }
+        rebuilt
+    }
+}
+
+fn values<'f, 'a>(fragment: &'f EntryFragment<'a>, key: &str) -> &'f [EntryData<'a>] {
+    fragment
+        .keys
+        .get(key)
+        .map(Node::as_slice)
+        .unwrap_or_default()
+}
+
+fn value<'f>(fragment: &'f EntryFragment<'_>, key: &str) -> Option<&'f str> {
+    values(fragment, key).first().and_then(EntryData::as_scalar)
+}
+
+fn sub_value<'f>(sub_entry: &'f SubEntry<'_>, key: &str) -> Option<&'f str> {
+    sub_entry
+        .keys
+        .get(key)
+        .and_then(|node| node.get(0))
+        .and_then(EntryData::as_scalar)
+}
+
+fn name_of<'f>(file: &'f EntryData<'_>) -> Option<&'f str> {
+    file.as_sub_entry()
+        .and_then(|sub_entry| sub_value(sub_entry, "name"))
+}
+
+/// Whether two ROMs or disks have the same name, and the same value for every hash they both have.
+fn same_file(a: &EntryData<'_>, b: &EntryData<'_>) -> bool {
+    let (a, b) = match (a.as_sub_entry(), b.as_sub_entry()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    sub_value(a, "name") == sub_value(b, "name")
+        && ["size", "crc", "md5", "sha1", "sha256"].iter().all(|key| {
+            match (sub_value(a, key), sub_value(b, key)) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => true,
+            }
+        })
+}
+
+/// The positions of the ROMs and disks of a set by name, so that a file is only compared with
+/// the files of the same name.
+#[derive(Debug, Default)]
+struct FileIndex<'s> {
+    named: IndexMap<&'static str, IndexMap<Cow<'s, str>, Vec<usize>>>,
+    unnamed: IndexMap<&'static str, Vec<usize>>,
+}
+
+impl<'s> FileIndex<'s> {
+    fn new(set: &'s EntryFragment<'_>) -> Self {
+        let mut index = FileIndex::default();
+        for &key in FILE_KEYS.iter() {
+            for (position, file) in values(set, key).iter().enumerate() {
+                if file.as_sub_entry().is_some() {
+                    index.insert(key, name_of(file).map(Cow::Borrowed), position);
+                }
+            }
+        }
+        index
+    }
+
+    fn into_owned(self) -> FileIndex<'static> {
+        let named = self
+            .named
+            .into_iter()
+            .map(|(key, names)| {
+                let names = names
+                    .into_iter()
+                    .map(|(name, positions)| (Cow::Owned(name.into_owned()), positions));
+                (key, names.collect())
+            })
+            .collect();
+        FileIndex {
+            named,
+            unnamed: self.unnamed,
+        }
+    }
+
+    fn insert(&mut self, key: &'static str, name: Option<Cow<'s, str>>, position: usize) {
+        match name {
+            Some(name) => self
+                .named
+                .entry(key)
+                .or_default()
+                .entry(name)
+                .or_default()
+                .push(position),
+            None => self.unnamed.entry(key).or_default().push(position),
+        }
+    }
+
+    /// Gets the positions of the sub-entries with the given key and name.
+    fn positions(&self, key: &str, name: Option<&str>) -> &[usize] {
+        let positions = match name {
+            Some(name) => self.named.get(key).and_then(|names| names.get(name)),
+            None => self.unnamed.get(key),
+        };
+        positions.map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn has_name(&self, key: &str, name: &str) -> bool {
+        !self.positions(key, Some(name)).is_empty()
+    }
+
+    /// Whether the indexed set has the same file as `file` under the given key.
+    fn contains(&self, set: &EntryFragment<'_>, key: &str, file: &EntryData<'_>) -> bool {
+        let files = values(set, key);
+        self.positions(key, name_of(file))
+            .iter()
+            .any(|&position| same_file(&files[position], file))
+    }
+
+    /// Adds a file to the end of the indexed set.
+    fn push<'a>(
+        &mut self,
+        set: &mut EntryFragment<'a>,
+        key: &'static str,
+        file: EntryData<'a>,
+        name: Option<Cow<'s, str>>,
+    ) {
+        if file.as_sub_entry().is_some() {
+            self.insert(key, name, values(set, key).len());
+        }
+        set.push(key, file);
+    }
+}
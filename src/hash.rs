@@ -0,0 +1,203 @@
+//! Fixed-size hash and checksum types for ROM and disk fields.
+//!
+//! `Crc32`, `Md5`, `Sha1` and `Sha256` parse from hex strings of exactly the right
+//! length in either case, and display as lowercase hex. With the `deserialize` feature
+//! they deserialize directly from the borrowed hex value without allocating, and with the
+//! `serialize` feature they serialize as hex strings.
+//!
+//! ```rust
+//! use listinfo::hash::Crc32;
+//!
+//! let crc: Crc32 = "C5A2A3F6".parse().unwrap();
+//! assert_eq!(crc, Crc32([0xc5, 0xa2, 0xa3, 0xf6]));
+//! assert_eq!(crc.to_string(), "c5a2a3f6");
+//! assert!("c5a2a3".parse::<Crc32>().is_err());
+//! ```
+
+use core::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use core::str::FromStr;
+
+use crate::error::ParseHashError;
+
+/// Decodes a hex string of exactly `2 * N` digits.
+fn decode<const N: usize>(s: &str) -> Result<[u8; N], ParseHashError> {
+    if s.len() != N * 2 {
+        return Err(ParseHashError::InvalidLength {
+            expected: N * 2,
+            found: s.len(),
+        });
+    }
+    let mut hash = [0; N];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = digit(s, i * 2)? << 4 | digit(s, i * 2 + 1)?;
+    }
+    Ok(hash)
+}
+
+fn digit(s: &str, index: usize) -> Result<u8, ParseHashError> {
+    match s.as_bytes()[index] {
+        b @ b'0'..=b'9' => Ok(b - b'0'),
+        b @ b'a'..=b'f' => Ok(b - b'a' + 10),
+        b @ b'A'..=b'F' => Ok(b - b'A' + 10),
+        // Every byte before this one is an ASCII digit, so `index` is on a character boundary.
+        _ => Err(ParseHashError::InvalidCharacter {
+            character: s[index..]
+                .chars()
+                .next()
+                .unwrap_or(char::REPLACEMENT_CHARACTER),
+            index,
+        }),
+    }
+}
+
+macro_rules! hash_type {
+    ($(#[$meta:meta])* $name:ident, $len:literal, $expecting:literal) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+        pub struct $name(pub [u8; $len]);
+
+        impl $name {
+            /// The length of the hash in bytes.
+            pub const LEN: usize = $len;
+
+            /// Gets the bytes of the hash.
+            pub fn as_bytes(&self) -> &[u8; $len] {
+                &self.0
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                $name(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(hash: $name) -> Self {
+                hash.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseHashError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                decode(s).map($name)
+            }
+        }
+
+        impl LowerHex for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                for byte in &self.0 {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl UpperHex for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                for byte in &self.0 {
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                LowerHex::fmt(self, f)
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({:x})", stringify!($name), self)
+            }
+        }
+
+        #[cfg(feature = "serialize")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        #[cfg(feature = "deserialize")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct HashVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for HashVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$name, E> {
+                        v.parse()
+                            .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                    }
+
+                    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<$name, E> {
+                        let mut hash = [0; $len];
+                        if v.len() != $len {
+                            return Err(E::invalid_length(v.len(), &self));
+                        }
+                        hash.copy_from_slice(v);
+                        Ok($name(hash))
+                    }
+                }
+
+                deserializer.deserialize_str(HashVisitor)
+            }
+        }
+    };
+}
+
+hash_type!(
+    /// A CRC32 checksum.
+    Crc32,
+    4,
+    "a CRC32 checksum of 8 hex digits"
+);
+
+hash_type!(
+    /// An MD5 hash.
+    Md5,
+    16,
+    "an MD5 hash of 32 hex digits"
+);
+
+hash_type!(
+    /// A SHA-1 hash.
+    Sha1,
+    20,
+    "a SHA-1 hash of 40 hex digits"
+);
+
+hash_type!(
+    /// A SHA-256 hash.
+    Sha256,
+    32,
+    "a SHA-256 hash of 64 hex digits"
+);
+
+impl From<u32> for Crc32 {
+    fn from(crc: u32) -> Self {
+        Crc32(crc.to_be_bytes())
+    }
+}
+
+impl From<Crc32> for u32 {
+    fn from(crc: Crc32) -> Self {
+        u32::from_be_bytes(crc.0)
+    }
+}
@@ -1,17 +1,18 @@
 //! Internal iterator types for ListInfo elements.
 
 use crate::elements::Node;
+use alloc::borrow::{Cow, ToOwned};
 use alloc::vec::Vec;
 use indexmap::map::Iter;
 
 /// Iterator that yields slices and string keys.
 pub struct SliceIter<'a, T> {
-    inner_iter: Iter<'a, &'a str, Vec<T>>,
+    inner_iter: Iter<'a, Cow<'a, str>, Vec<T>>,
 }
 
 impl<'a, T> SliceIter<'a, T> {
     #[doc(hidden)]
-    pub(crate) fn new(inner_iter: Iter<'a, &'a str, Vec<T>>) -> Self {
+    pub(crate) fn new(inner_iter: Iter<'a, Cow<'a, str>, Vec<T>>) -> Self {
         SliceIter { inner_iter }
     }
 }
@@ -19,11 +20,9 @@ impl<'a, T> SliceIter<'a, T> {
 impl<'a, T> Iterator for SliceIter<'a, T> {
     type Item = (&'a str, &'a [T]);
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((&k, v)) = self.inner_iter.next() {
-            Some((k, v))
-        } else {
-            None
-        }
+        self.inner_iter
+            .next()
+            .map(|(k, v)| (k.as_ref(), v.as_slice()))
     }
 }
 
@@ -39,17 +38,13 @@ impl<'a, K, V> EntryIter<'a, K, V> {
     }
 }
 
-impl<'a, K, V> Iterator for EntryIter<'a, &'a K, V>
+impl<'a, K, V> Iterator for EntryIter<'a, Cow<'a, K>, V>
 where
-    K: ?Sized,
+    K: ToOwned + ?Sized,
 {
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((&k, v)) = self.inner_iter.next() {
-            Some((k, v))
-        } else {
-            None
-        }
+        self.inner_iter.next().map(|(k, v)| (k.as_ref(), v))
     }
 }
 
@@ -2,53 +2,52 @@
 //!
 //! ## Usage
 //! listinfo-rs provides a lower-level zero-copy expression tree API
-//! as well as a more user friendly Serde deserialization API.
+//! as well as a more user friendly Serde deserialization and serialization API.
 //!
 //! Illustrated here is an example with the expression tree API
 //!
 //! ```rust
-//! #[test]
-//! fn parse_cave_story() {
-//!     const CAVE_STORY: &str = r#"clrmamepro (
-//!                 name "Cave Story"
-//!                 description "Cave Story"
-//!                 version 20161204
-//!                 comment "libretro | www.libretro.com"
-//!             )
-//!             game (
-//!                 name "Cave Story (En)"
-//!                 description "Cave Story (En)"
-//!                 developer "Studio Pixel"
-//!                 releaseyear "2004"
-//!                 rom (
-//!                     name "Doukutsu.exe"
-//!                     size 1478656
-//!                     crc c5a2a3f6
-//!                     md5 38695d3d69d7a0ada8178072dad4c58b
-//!                     sha1 bb2d0441e073da9c584f23c2ad8c7ab8aac293bf
-//!                 )
+//! use listinfo::{parse, EntryData};
+//!
+//! const CAVE_STORY: &str = r#"clrmamepro (
+//!             name "Cave Story"
+//!             description "Cave Story"
+//!             version 20161204
+//!             comment "libretro | www.libretro.com"
+//!         )
+//!         game (
+//!             name "Cave Story (En)"
+//!             description "Cave Story (En)"
+//!             developer "Studio Pixel"
+//!             releaseyear "2004"
+//!             rom (
+//!                 name "Doukutsu.exe"
+//!                 size 1478656
+//!                 crc c5a2a3f6
+//!                 md5 38695d3d69d7a0ada8178072dad4c58b
+//!                 sha1 bb2d0441e073da9c584f23c2ad8c7ab8aac293bf
 //!             )
-//!         "#;
+//!         )
+//!     "#;
 //!
-//!     let document = parse::parse_document(CAVE_STORY).unwrap();
-//!     let header = document.entry("clrmamepro").unwrap().next().unwrap();
-//!     let game = document.entry("game").unwrap().next().unwrap();
-//!     let rom = game.entry_unique("rom").unwrap();
-//!     assert_eq!(
-//!         header.entry_unique("name"),
-//!         Some(&EntryData::Scalar("Cave Story"))
-//!     );
-//!     assert_eq!(
-//!         game.entry_unique("name"),
-//!         Some(&EntryData::Scalar("Cave Story (En)"))
-//!     );
-//!     assert_eq!(
-//!         header.entry_unique("name"),
-//!         Some(&EntryData::Scalar("Cave Story"))
-//!     );
-//!     if let EntryData::SubEntry(rom) = rom {
-//!         assert_eq!(rom.value_unique("name"), Some("Doukutsu.exe"))
-//!     }
+//! let document = parse::parse_document(CAVE_STORY).unwrap();
+//! let header = document.entry("clrmamepro").unwrap().next().unwrap();
+//! let game = document.entry("game").unwrap().next().unwrap();
+//! let rom = game.entry_unique("rom").unwrap();
+//! assert_eq!(
+//!     header.entry_unique("name"),
+//!     Some(&EntryData::Scalar("Cave Story".into()))
+//! );
+//! assert_eq!(
+//!     game.entry_unique("name"),
+//!     Some(&EntryData::Scalar("Cave Story (En)".into()))
+//! );
+//! assert_eq!(
+//!     header.entry_unique("name"),
+//!     Some(&EntryData::Scalar("Cave Story".into()))
+//! );
+//! if let EntryData::SubEntry(rom) = rom {
+//!     assert_eq!(rom.value_unique("name"), Some("Doukutsu.exe"))
 //! }
 //! ```
 //!
@@ -56,6 +55,10 @@
 //! listinfo-rs supports the following features
 //!  * `std` Enables `std` support (enabled by default)
 //!  * `deserialize` Enables support for serde deserialization
+//!  * `serialize` Enables support for serde serialization
+//!  * `model` Enables the typed model of the standard clrmamepro vocabulary
+//!  * `logiqx` Enables reading and writing Logiqx XML DATs (requires `std`)
+//!  * `listxml` Enables importing the XML output of `mame -listxml` (requires `std`)
 //!
 //! ## `no_std`
 //! listinfo-rs requires `alloc`, but otherwise is fully supported on `#![no_std]`
@@ -64,7 +67,7 @@
 //! You can enable `no_std` support like in Cargo.toml
 //!
 //! ```toml
-//! listinfo = { version = "0.3", default-features = false }
+//! listinfo = { version = "0.4", default-features = false }
 //! ```
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -77,15 +80,36 @@ extern crate std as alloc;
 mod elements;
 mod error;
 
+pub mod cst;
+pub mod graph;
+pub mod hash;
 pub mod iter;
 
 #[cfg(test)]
 mod tests;
 
 pub mod parse;
+pub mod romcenter;
+pub mod span;
+pub mod write;
 
 #[cfg(feature = "deserialize")]
 pub mod de;
 
+#[cfg(feature = "serialize")]
+pub mod ser;
+
+#[cfg(feature = "model")]
+pub mod model;
+
+#[cfg(feature = "logiqx")]
+pub mod logiqx;
+
+#[cfg(feature = "listxml")]
+pub mod listxml;
+
+#[cfg(any(feature = "logiqx", feature = "listxml"))]
+mod xml;
+
 pub use elements::*;
 pub use error::*;
@@ -0,0 +1,76 @@
+//! Importing the XML output of `mame -listxml` into the element model.
+//!
+//! This must first be enabled in Cargo.toml
+//!
+//! ```toml
+//! listinfo = { version = "0.4", features = ["listxml"] }
+//! ```
+//!
+//! Every `<machine>` is read as a `machine` fragment, and the attributes of the `<mame>` root
+//! element, such as `build`, are read as a `mame` fragment. Elements are read the same way as
+//! [Logiqx XML](../logiqx/index.html) elements: attributes are read as values, elements that
+//! only contain text are read as values, and all other elements are read as sub-entries,
+//! nested as deep as they are in the XML. The text of an element that also has attributes,
+//! such as `<ramoption>`, is read as its `text` value.
+//!
+//! ```rust
+//! use listinfo::listxml;
+//!
+//! const LISTXML: &str = r#"<?xml version="1.0"?>
+//! <mame build="0.250 (mame0250)" debug="no" mameconfig="10">
+//!     <machine name="pacman" sourcefile="pacman/pacman.cpp">
+//!         <description>Pac-Man (Midway)</description>
+//!         <rom name="pacman.6e" size="4096" crc="c1e6ab10" region="maincpu" offset="0"/>
+//!         <device_ref name="z80"/>
+//!         <input players="2" coins="2">
+//!             <control type="joy" ways="4"/>
+//!         </input>
+//!         <driver status="good" emulation="good" savestate="supported"/>
+//!     </machine>
+//! </mame>"#;
+//!
+//! let document = listxml::parse_document(LISTXML).unwrap();
+//! let mame = document.entry("mame").unwrap().next().unwrap();
+//! assert_eq!(mame.entry_unique("build").and_then(|b| b.as_scalar()), Some("0.250 (mame0250)"));
+//!
+//! let pacman = document.entry("machine").unwrap().next().unwrap();
+//! let driver = pacman.entry_unique("driver").and_then(|d| d.as_sub_entry()).unwrap();
+//! assert_eq!(driver.value_unique("status"), Some("good"));
+//!
+//! let input = pacman.entry_unique("input").and_then(|i| i.as_sub_entry()).unwrap();
+//! let control = input.entry_unique("control").and_then(|c| c.as_sub_entry()).unwrap();
+//! assert_eq!(control.value_unique("ways"), Some("4"));
+//! ```
+
+use alloc::format;
+
+use crate::elements::*;
+use crate::error::Error;
+use crate::xml;
+
+/// Parses the XML output of `mame -listxml`.
+pub fn parse_document(input: &str) -> Result<DatDocument<'_>, Error> {
+    let (root, machines) = xml::read_document(input)?;
+    if root.name != "mame" {
+        return Err(Error::XmlError(format!(
+            "expected a <mame> element, found <{}>",
+            root.name
+        )));
+    }
+
+    let mut document = DatDocument::new();
+    if !root.entries.keys.is_empty() {
+        document.push(
+            "mame",
+            EntryFragment {
+                keys: root.entries.keys,
+            },
+        );
+    }
+    for (key, fragments) in machines.document {
+        for fragment in fragments {
+            document.push(key.clone(), fragment);
+        }
+    }
+    Ok(document)
+}
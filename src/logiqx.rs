@@ -0,0 +1,105 @@
+//! Reading and writing Logiqx XML DAT files.
+//!
+//! This must first be enabled in Cargo.toml
+//!
+//! ```toml
+//! listinfo = { version = "0.4", features = ["logiqx"] }
+//! ```
+//!
+//! Logiqx XML is the format published by No-Intro, Redump and most modern ROM managers.
+//! The `<header>` of a datafile is read as a `clrmamepro` fragment, with the attributes of its
+//! `<clrmamepro>` element read as values of the header. Every other element of the datafile,
+//! such as `<game>` or `<machine>`, is read as a fragment with the same key.
+//!
+//! Within a fragment, attributes are read as values, and child elements are read as
+//! values if they only contain text, such as `<description>`, or as sub-entries otherwise,
+//! such as `<rom name="..." crc="..."/>`. The text of an element that also has attributes is
+//! read as its `text` value. Text is only copied if it contained an escape sequence.
+//! `<sample>` and `<archive>` elements that only have a `name` are read as values, as they
+//! are written.
+//!
+//! ```rust
+//! use listinfo::logiqx;
+//!
+//! const DAT: &str = r#"<?xml version="1.0"?>
+//! <datafile>
+//!     <header>
+//!         <name>Cave Story</name>
+//!         <clrmamepro forcemerging="split"/>
+//!     </header>
+//!     <game name="Cave Story (En)">
+//!         <description>Cave Story (En)</description>
+//!         <rom name="Doukutsu.exe" size="1478656" crc="c5a2a3f6"/>
+//!     </game>
+//! </datafile>"#;
+//!
+//! let document = logiqx::parse_document(DAT).unwrap();
+//! let header = document.entry("clrmamepro").unwrap().next().unwrap();
+//! assert_eq!(header.entry_unique("forcemerging").and_then(|m| m.as_scalar()), Some("split"));
+//!
+//! let game = document.entry("game").unwrap().next().unwrap();
+//! let rom = game.entry_unique("rom").and_then(|rom| rom.as_sub_entry()).unwrap();
+//! assert_eq!(rom.value_unique("crc"), Some("c5a2a3f6"));
+//! ```
+//!
+//! Documents can be written back out as Logiqx XML with [`write_document`](fn.write_document.html)
+//! or [`to_string`](fn.to_string.html), whether they were read from XML or ListInfo DATs.
+//!
+//! ```rust
+//! use listinfo::{logiqx, parse};
+//!
+//! let document = parse::parse_document(r#"game (
+//!     name "Cave Story (En)"
+//!     description "Cave Story (En)"
+//!     rom ( name Doukutsu.exe size 1478656 )
+//! )"#).unwrap();
+//! let xml = logiqx::to_string(&document).unwrap();
+//! assert!(xml.contains(r#"<game name="Cave Story (En)">"#));
+//! assert!(xml.contains(r#"<rom name="Doukutsu.exe" size="1478656"/>"#));
+//! assert_eq!(logiqx::parse_document(&xml).unwrap(), document);
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::format;
+
+use crate::elements::*;
+use crate::error::Error;
+use crate::xml;
+
+mod write;
+
+pub use write::{to_string, write_document};
+
+/// Parses a Logiqx XML datafile.
+pub fn parse_document(input: &str) -> Result<DatDocument<'_>, Error> {
+    let (root, mut document) = xml::read_document(input)?;
+    if root.name != "datafile" {
+        return Err(Error::XmlError(format!(
+            "expected a <datafile> element, found <{}>",
+            root.name
+        )));
+    }
+    for fragment in document.document.values_mut().flatten() {
+        for &key in write::NAMED_ELEMENTS.iter() {
+            for data in fragment
+                .keys
+                .get_mut(key)
+                .into_iter()
+                .flat_map(Node::iter_mut)
+            {
+                if let Some(name) = data.as_sub_entry().and_then(name_only) {
+                    *data = EntryData::Scalar(name.clone());
+                }
+            }
+        }
+    }
+    Ok(document)
+}
+
+/// Gets the name of an element that has no other attributes.
+fn name_only<'e, 'a>(element: &'e SubEntry<'a>) -> Option<&'e Cow<'a, str>> {
+    match element.keys.get("name") {
+        Some(Node::Unique(EntryData::Scalar(name))) if element.keys.len() == 1 => Some(name),
+        _ => None,
+    }
+}
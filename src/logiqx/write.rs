@@ -0,0 +1,349 @@
+//! Writing documents as Logiqx XML DAT files.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
+
+use indexmap::IndexMap;
+
+use crate::elements::*;
+use crate::write::WriteOptions;
+
+const DOCTYPE: &str = r#"<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">"#;
+
+/// The order of the child elements of `<header>`.
+const HEADER_ORDER: [&str; 12] = [
+    "name",
+    "description",
+    "category",
+    "version",
+    "date",
+    "author",
+    "email",
+    "homepage",
+    "url",
+    "comment",
+    "clrmamepro",
+    "romcenter",
+];
+
+/// The child elements of `<header>` that the DTD requires.
+const HEADER_REQUIRED: [&str; 4] = ["name", "description", "version", "author"];
+
+/// The values of the header written as attributes of its `<clrmamepro>` element.
+const CLRMAMEPRO_ATTRIBUTES: [&str; 4] = ["header", "forcemerging", "forcenodump", "forcepacking"];
+
+/// The order of the child elements of `<game>`.
+const GAME_ORDER: [&str; 10] = [
+    "comment",
+    "description",
+    "year",
+    "manufacturer",
+    "release",
+    "biosset",
+    "rom",
+    "disk",
+    "sample",
+    "archive",
+];
+
+/// The child elements of `<game>` that may only appear once.
+const GAME_SINGLE: [&str; 3] = ["description", "year", "manufacturer"];
+
+/// The values of a set written as attributes.
+const GAME_ATTRIBUTES: [&str; 13] = [
+    "name",
+    "id",
+    "sourcefile",
+    "isbios",
+    "isdevice",
+    "ismechanical",
+    "runnable",
+    "cloneof",
+    "cloneofid",
+    "romof",
+    "sampleof",
+    "board",
+    "rebuildto",
+];
+
+/// Values of a set that are written as elements with a `name` attribute.
+pub(super) const NAMED_ELEMENTS: [&str; 2] = ["sample", "archive"];
+
+/// The attributes of the elements that sub-entries are written as, and how many of the
+/// first attributes are required.
+const ELEMENT_ATTRIBUTES: [(&str, &[&str], usize); 8] = [
+    (
+        "rom",
+        &[
+            "name", "size", "crc", "sha1", "md5", "merge", "status", "date",
+        ],
+        2,
+    ),
+    ("disk", &["name", "sha1", "md5", "merge", "status"], 1),
+    (
+        "release",
+        &["name", "region", "language", "date", "default"],
+        2,
+    ),
+    ("biosset", &["name", "description", "default"], 2),
+    ("sample", &["name"], 1),
+    ("archive", &["name"], 1),
+    ("clrmamepro", &CLRMAMEPRO_ATTRIBUTES, 0),
+    (
+        "romcenter",
+        &[
+            "plugin",
+            "rommode",
+            "biosmode",
+            "samplemode",
+            "lockrommode",
+            "lockbiosmode",
+            "locksamplemode",
+        ],
+        0,
+    ),
+];
+
+/// Displays a value with the characters that are special in XML escaped.
+struct XmlEscaped<'a> {
+    value: &'a str,
+    attribute: bool,
+}
+
+impl<'a> Display for XmlEscaped<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in self.value.char_indices() {
+            let escaped = match c {
+                '&' => "&amp;",
+                '<' => "&lt;",
+                '>' => "&gt;",
+                '"' if self.attribute => "&quot;",
+                '\t' if self.attribute => "&#9;",
+                '\n' if self.attribute => "&#10;",
+                '\r' => "&#13;",
+                _ => continue,
+            };
+            f.write_str(&self.value[start..i])?;
+            f.write_str(escaped)?;
+            start = i + c.len_utf8();
+        }
+        f.write_str(&self.value[start..])
+    }
+}
+
+/// Writes the document as a Logiqx XML datafile.
+///
+/// The first `clrmamepro` fragment is written as the `<header>`, with its `header`,
+/// `forcemerging`, `forcenodump` and `forcepacking` values as attributes of a `<clrmamepro>`
+/// element. `game` fragments are written as `<game>` elements, and `resource` fragments as
+/// `<game isbios="yes">`, with the values that Logiqx defines as attributes, such as `name`
+/// and `cloneof`, written as attributes and other values as child elements. Fragments with
+/// other keys are not written, since the DTD has no element for them.
+///
+/// Sub-entries such as `rom` are written as elements with their values as attributes.
+/// `sample` and `archive` values are written as elements with a `name` attribute, which
+/// [`parse_document`](fn.parse_document.html) reads back as values. Child elements are
+/// written in the order of the Logiqx DTD. Keys and attributes that the DTD does not define,
+/// repeats of elements that may only appear once, and sub-entries or repeated keys within
+/// sub-entries are not written. Elements and attributes that the DTD requires, such as the
+/// `description` of a game or the `author` of the header, are written empty if the document
+/// has no value for them.
+///
+/// Only the indentation and line endings of the options are used.
+pub fn write_document<W: Write>(
+    document: &DatDocument<'_>,
+    w: &mut W,
+    options: &WriteOptions,
+) -> fmt::Result {
+    w.write_str(r#"<?xml version="1.0"?>"#)?;
+    options.write_newline(w)?;
+    w.write_str(DOCTYPE)?;
+    options.write_newline(w)?;
+    w.write_str("<datafile>")?;
+    options.write_newline(w)?;
+
+    let empty = EntryData::Scalar(Cow::Borrowed(""));
+    // A datafile has a single header.
+    if let Some(header) = document.document.get("clrmamepro").and_then(|h| h.first()) {
+        let (attributes, mut children) =
+            split(&header.keys, |key| CLRMAMEPRO_ATTRIBUTES.contains(&key));
+        let has_clrmamepro = !attributes.is_empty();
+        let clrmamepro = EntryData::SubEntry(SubEntry {
+            keys: attributes
+                .into_iter()
+                .map(|(key, value)| (Cow::Borrowed(key), Node::Unique(value.into())))
+                .collect(),
+        });
+        if has_clrmamepro {
+            children.push(("clrmamepro", &clrmamepro));
+        }
+        require(&mut children, &HEADER_REQUIRED, &empty);
+        sort(&mut children, &HEADER_ORDER, &HEADER_ORDER);
+        write_element(w, options, 1, "header", &[], &children)?;
+    }
+    for (key, fragments) in document.document.iter() {
+        let isbios = match &**key {
+            "game" => false,
+            "resource" => true,
+            _ => continue,
+        };
+        for fragment in fragments {
+            let (mut attributes, mut children) =
+                split(&fragment.keys, |key| GAME_ATTRIBUTES.contains(&key));
+            if isbios {
+                attributes.retain(|(key, _)| *key != "isbios");
+                attributes.push(("isbios", "yes"));
+            }
+            if !attributes.iter().any(|(key, _)| *key == "name") {
+                attributes.push(("name", ""));
+            }
+            attributes
+                .sort_by_key(|(key, _)| GAME_ATTRIBUTES.iter().position(|known| known == key));
+            require(&mut children, &["description"], &empty);
+            sort(&mut children, &GAME_ORDER, &GAME_SINGLE);
+            write_element(w, options, 1, "game", &attributes, &children)?;
+        }
+    }
+
+    w.write_str("</datafile>")?;
+    options.write_newline(w)
+}
+
+/// Writes the document as a Logiqx XML datafile with the default options.
+pub fn to_string(document: &DatDocument<'_>) -> Result<String, fmt::Error> {
+    let mut xml = String::new();
+    write_document(document, &mut xml, &WriteOptions::default())?;
+    Ok(xml)
+}
+
+type Attributes<'e> = Vec<(&'e str, &'e str)>;
+type Children<'e, 'a> = Vec<(&'e str, &'e EntryData<'a>)>;
+
+/// Splits entries into the attributes and child elements of an element.
+///
+/// Only the first value of a key can be written as an attribute.
+fn split<'e, 'a>(
+    keys: &'e IndexMap<Cow<'a, str>, Node<EntryData<'a>>>,
+    is_attribute: impl Fn(&str) -> bool,
+) -> (Attributes<'e>, Children<'e, 'a>) {
+    let mut attributes = Vec::new();
+    let mut children = Vec::new();
+    for (key, node) in keys.iter() {
+        for (i, data) in node.iter().enumerate() {
+            match data {
+                EntryData::Scalar(value) if i == 0 && is_attribute(key) => {
+                    attributes.push((&**key, &**value))
+                }
+                _ => children.push((&**key, data)),
+            }
+        }
+    }
+    (attributes, children)
+}
+
+/// Adds an empty value for each of the required child elements that is missing.
+fn require<'e, 'a>(
+    children: &mut Children<'e, 'a>,
+    required: &[&'e str],
+    empty: &'e EntryData<'a>,
+) {
+    for key in required {
+        if !children.iter().any(|(child, _)| child == key) {
+            children.push((key, empty));
+        }
+    }
+}
+
+/// Keeps only the child elements in the given order, and the first of those that may only
+/// appear once, and sorts them into it.
+fn sort(children: &mut Children<'_, '_>, order: &[&str], single: &[&str]) {
+    children.retain(|(key, _)| order.contains(key));
+    children.sort_by_key(|(key, _)| order.iter().position(|known| known == key));
+    children.dedup_by(|(later, _), (earlier, _)| later == earlier && single.contains(later));
+}
+
+fn write_element<W: Write>(
+    w: &mut W,
+    options: &WriteOptions,
+    depth: usize,
+    name: &str,
+    attributes: &[(&str, &str)],
+    children: &[(&str, &EntryData<'_>)],
+) -> fmt::Result {
+    options.write_indent(w, depth)?;
+    write!(w, "<{}", name)?;
+    for (key, value) in attributes {
+        let value = XmlEscaped {
+            value,
+            attribute: true,
+        };
+        write!(w, " {}=\"{}\"", key, value)?;
+    }
+    if children.is_empty() {
+        w.write_str("/>")?;
+        return options.write_newline(w);
+    }
+    w.write_char('>')?;
+    options.write_newline(w)?;
+    for (key, data) in children {
+        write_child(w, options, depth + 1, key, data)?;
+    }
+    options.write_indent(w, depth)?;
+    write!(w, "</{}>", name)?;
+    options.write_newline(w)
+}
+
+fn write_child<W: Write>(
+    w: &mut W,
+    options: &WriteOptions,
+    depth: usize,
+    key: &str,
+    data: &EntryData<'_>,
+) -> fmt::Result {
+    let element = ELEMENT_ATTRIBUTES
+        .iter()
+        .find(|(element, _, _)| *element == key);
+    match (element, data) {
+        (Some(_), EntryData::Scalar(value)) if NAMED_ELEMENTS.contains(&key) => {
+            write_element(w, options, depth, key, &[("name", value)], &[])
+        }
+        (Some((_, names, required)), EntryData::SubEntry(sub_entry)) => {
+            let attributes: Attributes<'_> = names
+                .iter()
+                .enumerate()
+                .filter_map(|(i, name)| {
+                    let value = sub_entry
+                        .keys
+                        .get(*name)
+                        .and_then(|node| node.iter().next());
+                    match value {
+                        Some(EntryData::Scalar(value)) => Some((*name, &**value)),
+                        _ if i < *required => Some((*name, "")),
+                        _ => None,
+                    }
+                })
+                .collect();
+            write_element(w, options, depth, key, &attributes, &[])
+        }
+        // Elements that only have attributes can not be written from a value, and elements
+        // that only have text can not be written from a sub-entry.
+        (Some(_), _) | (None, EntryData::SubEntry(_)) => Ok(()),
+        (None, EntryData::Scalar(value)) if value.is_empty() => {
+            options.write_indent(w, depth)?;
+            write!(w, "<{}/>", key)?;
+            options.write_newline(w)
+        }
+        (None, EntryData::Scalar(value)) => {
+            let value = XmlEscaped {
+                value,
+                attribute: false,
+            };
+            options.write_indent(w, depth)?;
+            write!(w, "<{0}>{1}</{0}>", key, value)?;
+            options.write_newline(w)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use super::{Disk, Fields, Result, Rom};
+use crate::elements::*;
+use crate::Error;
+
+/// The key of a set fragment.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameKind {
+    /// A `game` fragment.
+    #[default]
+    Game,
+    /// A `machine` fragment, as written by newer MAME versions.
+    Machine,
+    /// A `resource` fragment, such as a BIOS or device set.
+    Resource,
+}
+
+impl GameKind {
+    /// The key used for the fragment in DAT files.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameKind::Game => "game",
+            GameKind::Machine => "machine",
+            GameKind::Resource => "resource",
+        }
+    }
+}
+
+impl Display for GameKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GameKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "game" => Ok(GameKind::Game),
+            "machine" => Ok(GameKind::Machine),
+            "resource" => Ok(GameKind::Resource),
+            _ => Err(Error::ModelError(format!("unknown set kind \"{}\"", s))),
+        }
+    }
+}
+
+/// A `game`, `machine` or `resource` fragment of a DAT.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Game {
+    /// The key of the fragment.
+    pub kind: GameKind,
+    /// The name of the set.
+    pub name: String,
+    /// A description of the set.
+    pub description: Option<String>,
+    /// A comment about the set.
+    pub comment: Option<String>,
+    /// The year the set was released.
+    pub year: Option<String>,
+    /// The manufacturer of the set.
+    pub manufacturer: Option<String>,
+    /// The name of the parent set, if this set is a clone.
+    pub cloneof: Option<String>,
+    /// The name of the set that ROMs are merged from, such as the parent or a BIOS.
+    pub romof: Option<String>,
+    /// The name of the set that samples are shared from.
+    pub sampleof: Option<String>,
+    /// The releases of the set.
+    pub releases: Vec<Release>,
+    /// The BIOS options of the set.
+    pub biossets: Vec<BiosSet>,
+    /// The ROMs of the set.
+    pub roms: Vec<Rom>,
+    /// The disks of the set.
+    pub disks: Vec<Disk>,
+    /// The samples used by the set.
+    pub samples: Vec<Sample>,
+    /// The archives of the set.
+    pub archives: Vec<Archive>,
+}
+
+impl Game {
+    /// Converts a set fragment with the given key into a game.
+    pub fn from_fragment(kind: GameKind, fragment: &EntryFragment<'_>) -> Result<Self> {
+        let fields = Fields::new(&fragment.keys, kind.as_str());
+        Ok(Game {
+            kind,
+            name: fields.required("name")?,
+            description: fields.string("description")?,
+            comment: fields.string("comment")?,
+            year: fields.string("year")?,
+            manufacturer: fields.string("manufacturer")?,
+            cloneof: fields.string("cloneof")?,
+            romof: fields.string("romof")?,
+            sampleof: fields.string("sampleof")?,
+            releases: fields.sub_entries("release", Release::from_fields)?,
+            biossets: fields.sub_entries("biosset", BiosSet::from_fields)?,
+            roms: fields.sub_entries("rom", Rom::from_fields)?,
+            disks: fields.sub_entries("disk", Disk::from_fields)?,
+            samples: fields.list("sample", Sample::from_entry_data)?,
+            archives: fields.list("archive", Archive::from_entry_data)?,
+        })
+    }
+}
+
+/// A `release` sub-entry of a set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Release {
+    /// The name of the release.
+    pub name: String,
+    /// The region of the release.
+    pub region: Option<String>,
+    /// The language of the release.
+    pub language: Option<String>,
+    /// The date of the release.
+    pub date: Option<String>,
+    /// Whether this is the default release of the set.
+    pub default: bool,
+}
+
+impl Release {
+    fn from_fields(fields: &Fields<'_, '_>) -> Result<Self> {
+        Ok(Release {
+            name: fields.required("name")?,
+            region: fields.string("region")?,
+            language: fields.string("language")?,
+            date: fields.string("date")?,
+            default: fields.flag("default")?,
+        })
+    }
+}
+
+/// A `biosset` sub-entry of a set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct BiosSet {
+    /// The name of the BIOS option.
+    pub name: String,
+    /// A description of the BIOS option.
+    pub description: Option<String>,
+    /// Whether this is the default BIOS option of the set.
+    pub default: bool,
+}
+
+impl BiosSet {
+    fn from_fields(fields: &Fields<'_, '_>) -> Result<Self> {
+        Ok(BiosSet {
+            name: fields.required("name")?,
+            description: fields.string("description")?,
+            default: fields.flag("default")?,
+        })
+    }
+}
+
+/// A `sample` used by a set.
+///
+/// Samples may be written either as `sample name` or `sample ( name name )`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Sample {
+    /// The name of the sample.
+    pub name: String,
+}
+
+impl Sample {
+    fn from_entry_data(data: &EntryData<'_>) -> Result<Self> {
+        Ok(Sample {
+            name: name_of("sample", data)?,
+        })
+    }
+}
+
+/// An `archive` of a set.
+///
+/// Archives may be written either as `archive name` or `archive ( name name )`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Archive {
+    /// The name of the archive.
+    pub name: String,
+}
+
+impl Archive {
+    fn from_entry_data(data: &EntryData<'_>) -> Result<Self> {
+        Ok(Archive {
+            name: name_of("archive", data)?,
+        })
+    }
+}
+
+/// Reads a name that is either the value itself, or the `name` of a sub-entry.
+fn name_of(key: &str, data: &EntryData<'_>) -> Result<String> {
+    match data {
+        EntryData::Scalar(name) => Ok(String::from(name.as_ref())),
+        EntryData::SubEntry(sub_entry) => Fields::new(&sub_entry.keys, key).required("name"),
+    }
+}
@@ -0,0 +1,117 @@
+use alloc::format;
+use alloc::string::String;
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use super::{Fields, Result};
+use crate::elements::*;
+use crate::Error;
+
+/// How ROM sets should be merged, as forced by the DAT header.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ForceMerging {
+    /// Every set contains all of its ROMs, including those of its parent.
+    None,
+    /// Clones only contain the ROMs that differ from their parent.
+    Split,
+    /// Clones are merged into the set of their parent.
+    Full,
+}
+
+impl ForceMerging {
+    /// The keyword used for the merging mode in DAT files.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForceMerging::None => "none",
+            ForceMerging::Split => "split",
+            ForceMerging::Full => "full",
+        }
+    }
+}
+
+impl Display for ForceMerging {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ForceMerging {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(ForceMerging::None),
+            "split" => Ok(ForceMerging::Split),
+            "full" => Ok(ForceMerging::Full),
+            _ => Err(Error::ModelError(format!("unknown merging mode \"{}\"", s))),
+        }
+    }
+}
+
+/// The `clrmamepro` header fragment of a DAT.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Header {
+    /// The name of the DAT.
+    pub name: String,
+    /// A description of the DAT.
+    pub description: Option<String>,
+    /// The category of the DAT.
+    pub category: Option<String>,
+    /// The version of the DAT.
+    pub version: Option<String>,
+    /// The date the DAT was published.
+    pub date: Option<String>,
+    /// The author of the DAT.
+    pub author: Option<String>,
+    /// The contact email of the author.
+    pub email: Option<String>,
+    /// The homepage of the author.
+    pub homepage: Option<String>,
+    /// The URL the DAT was published at.
+    pub url: Option<String>,
+    /// A comment about the DAT.
+    pub comment: Option<String>,
+    /// The skipper header file used to verify ROMs.
+    pub header: Option<String>,
+    /// How ROM sets should be merged.
+    pub forcemerging: Option<ForceMerging>,
+    /// How `nodump` ROMs should be treated.
+    pub forcenodump: Option<String>,
+    /// How sets should be packed.
+    pub forcepacking: Option<String>,
+}
+
+impl Header {
+    /// Converts a `clrmamepro` fragment into a header.
+    pub fn from_fragment(fragment: &EntryFragment<'_>) -> Result<Self> {
+        Self::from_fields(&Fields::new(&fragment.keys, "clrmamepro"))
+    }
+
+    fn from_fields(fields: &Fields<'_, '_>) -> Result<Self> {
+        Ok(Header {
+            name: fields.required("name")?,
+            description: fields.string("description")?,
+            category: fields.string("category")?,
+            version: fields.string("version")?,
+            date: fields.string("date")?,
+            author: fields.string("author")?,
+            email: fields.string("email")?,
+            homepage: fields.string("homepage")?,
+            url: fields.string("url")?,
+            comment: fields.string("comment")?,
+            header: fields.string("header")?,
+            forcemerging: fields.parse("forcemerging")?,
+            forcenodump: fields.string("forcenodump")?,
+            forcepacking: fields.string("forcepacking")?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&EntryFragment<'a>> for Header {
+    type Error = Error;
+
+    fn try_from(fragment: &EntryFragment<'a>) -> Result<Self> {
+        Header::from_fragment(fragment)
+    }
+}
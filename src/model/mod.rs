@@ -0,0 +1,231 @@
+//! Typed model of the standard clrmamepro DAT vocabulary.
+//!
+//! This must first be enabled in Cargo.toml
+//!
+//! ```toml
+//! listinfo = { version = "0.4", features = ["model"] }
+//! ```
+//!
+//! The model covers the `clrmamepro` header, the `game`, `machine` and `resource` fragments,
+//! and their `rom`, `disk`, `sample`, `archive`, `release` and `biosset` sub-entries.
+//! Conversions from the parsed expression tree do not require serde. Keys that are not part
+//! of the standard vocabulary are ignored.
+//!
+//! ```rust
+//! use listinfo::parse;
+//! use listinfo::model::{Datafile, Status};
+//!
+//! const DAT: &str = r#"clrmamepro ( name "Cave Story" version 20161204 )
+//!     game (
+//!         name "Cave Story (En)"
+//!         rom ( name Doukutsu.exe size 1478656 crc c5a2a3f6 )
+//!         rom ( name data.pak size 42 flags baddump )
+//!     )"#;
+//!
+//! let document = parse::parse_document(DAT).unwrap();
+//! let datafile = Datafile::from_document(&document).unwrap();
+//! assert_eq!(datafile.header.unwrap().version.as_deref(), Some("20161204"));
+//! assert_eq!(datafile.games[0].roms[0].size, Some(1478656));
+//! assert_eq!(datafile.games[0].roms[0].crc.unwrap().to_string(), "c5a2a3f6");
+//! assert_eq!(datafile.games[0].roms[1].status, Status::BadDump);
+//! ```
+
+mod game;
+mod header;
+mod rom;
+
+#[cfg(test)]
+mod tests;
+
+pub use game::*;
+pub use header::*;
+pub use rom::*;
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+use indexmap::IndexMap;
+
+use crate::elements::*;
+use crate::Error;
+
+type Result<T> = core::result::Result<T, Error>;
+
+/// The typed contents of a ListInfo DAT.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Datafile {
+    /// The `clrmamepro` header, if the DAT has one.
+    pub header: Option<Header>,
+    /// The `game`, `machine` and `resource` sets of the DAT.
+    pub games: Vec<Game>,
+}
+
+impl Datafile {
+    /// Converts a parsed document into its typed model.
+    ///
+    /// Only the first `clrmamepro` fragment is used as the header,
+    /// and fragments with other unknown keys are ignored.
+    pub fn from_document(document: &DatDocument<'_>) -> Result<Self> {
+        let mut datafile = Datafile::default();
+        for (key, fragments) in document.iter() {
+            if key == "clrmamepro" {
+                if let Some(fragment) = fragments.first() {
+                    datafile.header = Some(Header::from_fragment(fragment)?);
+                }
+            } else if let Ok(kind) = key.parse::<GameKind>() {
+                for fragment in fragments {
+                    datafile.games.push(Game::from_fragment(kind, fragment)?);
+                }
+            }
+        }
+        Ok(datafile)
+    }
+}
+
+/// The dump status of a ROM or disk.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Status {
+    /// The dump is believed to be good.
+    #[default]
+    Good,
+    /// The dump is known to be bad.
+    BadDump,
+    /// The data has not been dumped.
+    NoDump,
+    /// The dump has been verified to be good.
+    Verified,
+}
+
+impl Status {
+    /// The keyword used for the status in DAT files.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Good => "good",
+            Status::BadDump => "baddump",
+            Status::NoDump => "nodump",
+            Status::Verified => "verified",
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "good" => Ok(Status::Good),
+            "baddump" => Ok(Status::BadDump),
+            "nodump" => Ok(Status::NoDump),
+            "verified" => Ok(Status::Verified),
+            _ => Err(Error::ModelError(format!("unknown status \"{}\"", s))),
+        }
+    }
+}
+
+/// Reads the typed values of a fragment or sub-entry, naming it in errors.
+pub(crate) struct Fields<'e, 'a> {
+    entries: &'e IndexMap<Cow<'a, str>, Node<EntryData<'a>>>,
+    name: &'e str,
+}
+
+impl<'e, 'a> Fields<'e, 'a> {
+    fn new(entries: &'e IndexMap<Cow<'a, str>, Node<EntryData<'a>>>, name: &'e str) -> Self {
+        Fields { entries, name }
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::ModelError(format!("{} in {}", message, self.name))
+    }
+
+    /// Gets the first value with the given key.
+    fn str(&self, key: &str) -> Result<Option<&'e str>> {
+        match self.entries.get(key).and_then(|node| node.get(0)) {
+            Some(EntryData::Scalar(value)) => Ok(Some(value)),
+            Some(EntryData::SubEntry(_)) => Err(self.error(format!(
+                "expected a value for \"{}\", found a sub-entry",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.str(key)?.map(String::from))
+    }
+
+    fn required(&self, key: &str) -> Result<String> {
+        self.string(key)?
+            .ok_or_else(|| self.error(format!("missing \"{}\"", key)))
+    }
+
+    fn parse<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        self.str(key)?
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| self.error(format!("invalid value \"{}\" for \"{}\"", value, key)))
+            })
+            .transpose()
+    }
+
+    /// Reads a `yes`/`no` flag, which is `false` when missing.
+    fn flag(&self, key: &str) -> Result<bool> {
+        match self.str(key)? {
+            None | Some("no") => Ok(false),
+            Some("yes") => Ok(true),
+            Some(value) => Err(self.error(format!(
+                "invalid value \"{}\" for \"{}\", expected yes or no",
+                value, key
+            ))),
+        }
+    }
+
+    /// Reads the `status` of a ROM or disk, falling back to its `flags`.
+    fn status(&self) -> Result<Status> {
+        if let Some(status) = self.str("status")? {
+            return status
+                .parse()
+                .map_err(|_| self.error(format!("invalid value \"{}\" for \"status\"", status)));
+        }
+        Ok(self
+            .str("flags")?
+            .and_then(|flags| flags.parse().ok())
+            .unwrap_or_default())
+    }
+
+    /// Converts every value with the given key.
+    fn list<T>(
+        &self,
+        key: &str,
+        convert: impl FnMut(&'e EntryData<'a>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        match self.entries.get(key) {
+            Some(node) => node.iter().map(convert).collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Converts every sub-entry with the given key.
+    fn sub_entries<T>(
+        &self,
+        key: &str,
+        mut convert: impl FnMut(&Fields<'_, 'a>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        self.list(key, |data| match data {
+            EntryData::SubEntry(sub_entry) => convert(&Fields::new(&sub_entry.keys, key)),
+            EntryData::Scalar(_) => Err(self.error(format!(
+                "expected a sub-entry for \"{}\", found a value",
+                key
+            ))),
+        })
+    }
+}
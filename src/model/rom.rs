@@ -0,0 +1,108 @@
+use alloc::string::String;
+use core::convert::TryFrom;
+
+use super::{Fields, Result, Status};
+use crate::elements::*;
+use crate::hash::{Crc32, Md5, Sha1, Sha256};
+use crate::Error;
+
+/// A `rom` sub-entry of a set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Rom {
+    /// The file name of the ROM.
+    pub name: String,
+    /// The size of the ROM in bytes.
+    pub size: Option<u64>,
+    /// The CRC32 checksum of the ROM.
+    pub crc: Option<Crc32>,
+    /// The MD5 hash of the ROM.
+    pub md5: Option<Md5>,
+    /// The SHA-1 hash of the ROM.
+    pub sha1: Option<Sha1>,
+    /// The SHA-256 hash of the ROM.
+    pub sha256: Option<Sha256>,
+    /// The name of the ROM in the parent set that this ROM is merged with.
+    pub merge: Option<String>,
+    /// The BIOS set that this ROM belongs to.
+    pub bios: Option<String>,
+    /// The dump status of the ROM, read from `status` or otherwise `flags`.
+    pub status: Status,
+    /// The raw `flags` of the ROM.
+    pub flags: Option<String>,
+    /// The date of the ROM.
+    pub date: Option<String>,
+}
+
+impl Rom {
+    /// Converts a `rom` sub-entry into a ROM.
+    pub fn from_sub_entry(sub_entry: &SubEntry<'_>) -> Result<Self> {
+        Self::from_fields(&Fields::new(&sub_entry.keys, "rom"))
+    }
+
+    pub(crate) fn from_fields(fields: &Fields<'_, '_>) -> Result<Self> {
+        Ok(Rom {
+            name: fields.required("name")?,
+            size: fields.parse("size")?,
+            crc: fields.parse("crc")?,
+            md5: fields.parse("md5")?,
+            sha1: fields.parse("sha1")?,
+            sha256: fields.parse("sha256")?,
+            merge: fields.string("merge")?,
+            bios: fields.string("bios")?,
+            status: fields.status()?,
+            flags: fields.string("flags")?,
+            date: fields.string("date")?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&SubEntry<'a>> for Rom {
+    type Error = Error;
+
+    fn try_from(sub_entry: &SubEntry<'a>) -> Result<Self> {
+        Rom::from_sub_entry(sub_entry)
+    }
+}
+
+/// A `disk` sub-entry of a set, such as a CHD image.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Disk {
+    /// The name of the disk.
+    pub name: String,
+    /// The MD5 hash of the disk.
+    pub md5: Option<Md5>,
+    /// The SHA-1 hash of the disk.
+    pub sha1: Option<Sha1>,
+    /// The name of the disk in the parent set that this disk is merged with.
+    pub merge: Option<String>,
+    /// The dump status of the disk, read from `status` or otherwise `flags`.
+    pub status: Status,
+    /// The raw `flags` of the disk.
+    pub flags: Option<String>,
+}
+
+impl Disk {
+    /// Converts a `disk` sub-entry into a disk.
+    pub fn from_sub_entry(sub_entry: &SubEntry<'_>) -> Result<Self> {
+        Self::from_fields(&Fields::new(&sub_entry.keys, "disk"))
+    }
+
+    pub(crate) fn from_fields(fields: &Fields<'_, '_>) -> Result<Self> {
+        Ok(Disk {
+            name: fields.required("name")?,
+            md5: fields.parse("md5")?,
+            sha1: fields.parse("sha1")?,
+            merge: fields.string("merge")?,
+            status: fields.status()?,
+            flags: fields.string("flags")?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&SubEntry<'a>> for Disk {
+    type Error = Error;
+
+    fn try_from(sub_entry: &SubEntry<'a>) -> Result<Self> {
+        Disk::from_sub_entry(sub_entry)
+    }
+}
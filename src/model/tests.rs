@@ -0,0 +1,140 @@
+use super::*;
+use crate::hash::Crc32;
+use crate::parse;
+use alloc::string::ToString;
+
+const MAME: &str = r#"clrmamepro (
+        name "MAME"
+        description "MAME 0.78"
+        version 0.78
+        forcemerging split
+    )
+
+    resource (
+        name neogeo
+        description "Neo-Geo"
+        biosset ( name euro description "Europe MVS (Ver. 2)" default yes )
+        biosset ( name us description "US MVS (Ver. 2?)" )
+        rom ( name sp-s2.sp1 size 131072 crc 9036d879 bios euro )
+        rom ( name sm1.sm1 size 131072 crc 97cf998b flags nodump )
+    )
+
+    game (
+        name mslug
+        description "Metal Slug - Super Vehicle-001"
+        year 1996
+        manufacturer Nazca
+        romof neogeo
+        sample slug1
+        sample ( name slug2 )
+        rom ( name 201-p1.bin size 2097152 crc 08d8daa5 status verified )
+        disk ( name mslug md5 00112233445566778899aabbccddeeff )
+    )
+
+    game (
+        name mslugj
+        cloneof mslug
+        romof mslug
+        release ( name mslugj region JPN language ja default yes )
+        rom ( name 201-p1.bin merge 201-p1.bin size 2097152 crc 08d8daa5 )
+        archive mslug.zip
+    )"#;
+
+#[test]
+fn model_datafile() {
+    let document = parse::parse_document(MAME).unwrap();
+    let datafile = Datafile::from_document(&document).unwrap();
+
+    let header = datafile.header.unwrap();
+    assert_eq!(header.name, "MAME");
+    assert_eq!(header.version.as_deref(), Some("0.78"));
+    assert_eq!(header.forcemerging, Some(ForceMerging::Split));
+
+    let kinds: Vec<_> = datafile.games.iter().map(|game| game.kind).collect();
+    assert_eq!(kinds, [GameKind::Resource, GameKind::Game, GameKind::Game]);
+
+    let neogeo = &datafile.games[0];
+    assert_eq!(neogeo.biossets.len(), 2);
+    assert!(neogeo.biossets[0].default);
+    assert!(!neogeo.biossets[1].default);
+    assert_eq!(neogeo.roms[0].bios.as_deref(), Some("euro"));
+    assert_eq!(neogeo.roms[1].status, Status::NoDump);
+    assert_eq!(neogeo.roms[1].flags.as_deref(), Some("nodump"));
+
+    let mslug = &datafile.games[1];
+    assert_eq!(mslug.year.as_deref(), Some("1996"));
+    assert_eq!(mslug.romof.as_deref(), Some("neogeo"));
+    assert_eq!(mslug.cloneof, None);
+    let samples: Vec<_> = mslug.samples.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(samples, ["slug1", "slug2"]);
+    assert_eq!(mslug.roms[0].status, Status::Verified);
+    assert_eq!(mslug.roms[0].size, Some(2097152));
+    assert_eq!(mslug.roms[0].crc, Some(Crc32::from(0x08d8daa5)));
+    assert_eq!(
+        mslug.disks[0].md5.map(|md5| md5.to_string()).as_deref(),
+        Some("00112233445566778899aabbccddeeff")
+    );
+    assert_eq!(mslug.disks[0].status, Status::Good);
+
+    let mslugj = &datafile.games[2];
+    assert_eq!(mslugj.cloneof.as_deref(), Some("mslug"));
+    assert_eq!(mslugj.roms[0].merge.as_deref(), Some("201-p1.bin"));
+    assert_eq!(mslugj.releases[0].region.as_deref(), Some("JPN"));
+    assert!(mslugj.releases[0].default);
+    assert_eq!(mslugj.archives[0].name, "mslug.zip");
+}
+
+#[test]
+fn model_invalid_fragments() {
+    let (_, game) = parse::parse_fragment("game ( description foo )").unwrap();
+    let err = Game::from_fragment(GameKind::Game, &game).unwrap_err();
+    assert_eq!(err.to_string(), "missing \"name\" in game");
+
+    let (_, game) = parse::parse_fragment("machine ( name foo rom ( name a size big ) )").unwrap();
+    let err = Game::from_fragment(GameKind::Machine, &game).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value \"big\" for \"size\" in rom");
+
+    let (_, game) = parse::parse_fragment("game ( name foo rom bar )").unwrap();
+    let err = Game::from_fragment(GameKind::Game, &game).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a sub-entry for \"rom\", found a value in game"
+    );
+
+    let (_, game) = parse::parse_fragment("game ( name foo rom ( name a crc 0000 ) )").unwrap();
+    let err = Game::from_fragment(GameKind::Game, &game).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value \"0000\" for \"crc\" in rom");
+
+    let (_, game) =
+        parse::parse_fragment("game ( name foo disk ( name a status broken ) )").unwrap();
+    assert!(Game::from_fragment(GameKind::Game, &game).is_err());
+
+    assert_eq!("baddump".parse::<Status>().unwrap(), Status::BadDump);
+    assert_eq!(Status::NoDump.to_string(), "nodump");
+    assert!("machine".parse::<GameKind>().is_ok());
+    assert!("clrmamepro".parse::<GameKind>().is_err());
+}
+
+#[cfg(feature = "listxml")]
+#[test]
+fn model_listxml() {
+    const LISTXML: &str = r#"<mame build="0.250">
+        <machine name="pacman" sourcefile="pacman/pacman.cpp">
+            <description>Pac-Man (Midway)</description>
+            <year>1980</year>
+            <rom name="pacman.6e" size="4096" crc="c1e6ab10" sha1="e87e059c5be45753f7e9f33dff851f16d6751181" region="maincpu" offset="0"/>
+            <sample name="intro"/>
+            <driver status="good"/>
+        </machine>
+    </mame>"#;
+
+    let document = crate::listxml::parse_document(LISTXML).unwrap();
+    let datafile = Datafile::from_document(&document).unwrap();
+    assert_eq!(datafile.header, None);
+
+    let pacman = &datafile.games[0];
+    assert_eq!(pacman.kind, GameKind::Machine);
+    assert_eq!(pacman.year.as_deref(), Some("1980"));
+    assert_eq!(pacman.roms[0].crc, Some(Crc32::from(0xc1e6ab10)));
+    assert_eq!(pacman.samples[0].name, "intro");
+}
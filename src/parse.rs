@@ -1,176 +1,410 @@
 //! Parsing routines for MAME ListInfo DAT files.
 //!
 //! Most use cases should be covered by `parse_document`.
-//! `parse_fragment` only succeeds in parsing a single fragment.
+//! `parse_fragment` only succeeds in parsing a single fragment, and `fragments`
+//! lazily parses one fragment at a time without building a `DatDocument`.
+//! `Reader` does the same for buffered IO streams, keeping only the current fragment in memory.
 //!
 //! A "fragment" is a single grouping in a ListInfo DAT.
+//!
+//! The grammar is whitespace-agnostic: keys and values may be separated by any
+//! number of spaces or tabs, lines may end in `\n` or `\r\n`, and the opening
+//! parenthesis of a fragment or sub-entry may directly follow its key.
 
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, take_till1},
-    character::complete::{char, multispace0},
-    combinator::complete,
+    bytes::complete::{take_till1, take_while1},
+    character::complete::{char, multispace0, space0},
+    error::{ErrorKind, ParseError as _},
     multi::{many0, many1},
-    sequence::delimited,
-    IResult,
+    Err,
 };
 
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::result::Result;
 use indexmap::IndexMap;
 
 use crate::elements::*;
-use crate::error::Error;
+use crate::error::{Error, ParseError};
+
+#[cfg(feature = "std")]
+mod reader;
+
+#[cfg(feature = "std")]
+pub use reader::Reader;
+
+pub(crate) type IResult<'a, T> = nom::IResult<&'a str, T, DatError<'a>>;
+
+/// The deepest sub-entries can be nested within a fragment.
+///
+/// Deeper sub-entries fail to parse rather than overflowing the stack.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// The token that was expected when a parser failed.
+#[derive(Debug, Clone, Copy)]
+enum Expected {
+    Char(char),
+    Kind(ErrorKind),
+    Depth,
+}
+
+impl Expected {
+    fn describe(self) -> String {
+        match self {
+            Expected::Char(c) => format!("`{}`", c),
+            Expected::Kind(ErrorKind::TakeTill1) => String::from("a key or value"),
+            Expected::Kind(ErrorKind::TakeWhile1) => String::from("whitespace"),
+            Expected::Kind(ErrorKind::Many1) => String::from("an entry"),
+            Expected::Kind(kind) => String::from(kind.description()),
+            Expected::Depth => format!("at most {} nested sub-entries", MAX_DEPTH),
+        }
+    }
+}
+
+/// Parser error that records the remaining input and the expected token without allocating.
+#[derive(Debug)]
+pub(crate) struct DatError<'a> {
+    input: &'a str,
+    expected: Expected,
+}
+
+impl<'a> nom::error::ParseError<&'a str> for DatError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        DatError {
+            input,
+            expected: Expected::Kind(kind),
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        DatError {
+            input,
+            expected: Expected::Char(c),
+        }
+    }
+
+    fn or(self, other: Self) -> Self {
+        // Report whichever alternative made it furthest into the input, preferring the latter.
+        if other.input.len() <= self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a> DatError<'a> {
+    /// Creates the error for a sub-entry nested deeper than `MAX_DEPTH`.
+    pub(crate) fn too_deep(input: &'a str) -> Self {
+        DatError {
+            input,
+            expected: Expected::Depth,
+        }
+    }
+
+    /// Converts the error into a `ParseError` describing its position in the complete input.
+    fn into_parse_error(self, input: &str, fragment: &str) -> ParseError {
+        let offset = input.len() - self.input.len();
+        let (key, name) = fragment_context(fragment);
+        ParseError::new(
+            input,
+            offset,
+            self.expected.describe(),
+            key,
+            name.as_deref(),
+        )
+    }
+}
+
+pub(crate) fn to_error(err: Err<DatError<'_>>, input: &str, fragment: &str) -> Error {
+    match err {
+        Err::Incomplete(_) => Error::UnknownError,
+        Err::Error(e) | Err::Failure(e) => e.into_parse_error(input, fragment).into(),
+    }
+}
+
+/// Finds the key and `name` of the fragment at the start of the input, as far as it can be parsed.
+fn fragment_context(fragment: &str) -> (Option<&str>, Option<Cow<'_, str>>) {
+    let (input, key) = match string_key(fragment) {
+        Ok(parsed) => parsed,
+        Err(_) => return (None, None),
+    };
+    let mut input = match open_entry(input) {
+        Ok((input, _)) => input,
+        Err(_) => return (Some(key), None),
+    };
+    let mut name = None;
+    while let Ok((rest, (entry_key, value))) = parse_entry(input, 1) {
+        if let ("name", ParsedValue::Value(value)) = (entry_key, value) {
+            name = Some(value);
+        }
+        input = rest;
+    }
+    (Some(key), name)
+}
 
 #[derive(Debug)]
 enum ParsedValue<'a> {
     Subentry(Vec<(&'a str, ParsedValue<'a>)>),
-    Value(&'a str),
+    Value(Cow<'a, str>),
 }
 
-fn open_entry(input: &str) -> IResult<&str, char> {
+pub(crate) fn open_entry(input: &str) -> IResult<'_, char> {
     let (input, _) = multispace0(input)?;
     let (input, open) = char('(')(input)?;
     Ok((input, open))
 }
 
-fn close_entry(input: &str) -> IResult<&str, char> {
+pub(crate) fn close_entry(input: &str) -> IResult<'_, char> {
     let (input, _) = multispace0(input)?;
     let (input, close) = char(')')(input)?;
     Ok((input, close))
 }
 
-fn subentry_contents(input: &str) -> IResult<&str, Vec<(&str, ParsedValue)>> {
-    let (input, _) = multispace0(input)?;
-    let (input, _) = char('(')(input)?;
-    let (input, results) = many1(parse_string_value)(input)?;
+fn subentry_contents(input: &str, depth: usize) -> IResult<'_, Vec<(&str, ParsedValue<'_>)>> {
+    let (input, _) = space0(input)?;
+    let (rest, _) = char('(')(input)?;
+    if depth > MAX_DEPTH {
+        // Fail without backtracking, so the value is not parsed as a scalar instead.
+        return Err(Err::Failure(DatError::too_deep(input)));
+    }
+    let input = rest;
+    let (input, results) = many1(|input| parse_entry(input, depth + 1))(input)?;
     let (input, _) = multispace0(input)?;
     let (input, _) = char(')')(input)?;
     Ok((input, results))
 }
 
-fn quoted_string(input: &str) -> IResult<&str, &str> {
-    delimited(char('"'), is_not("\""), char('"'))(input)
+/// Parses a quoted string, unescaping `\"` and `\\`.
+///
+/// The value is only copied if it contained an escape sequence.
+/// Backslashes that do not start an escape sequence are kept as-is.
+pub(crate) fn quoted_string(input: &str) -> IResult<'_, Cow<'_, str>> {
+    let (input, _) = char('"')(input)?;
+    let mut unescaped: Option<String> = None;
+    let mut start = 0;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let value = match unescaped {
+                    Some(mut value) => {
+                        value.push_str(&input[start..i]);
+                        Cow::Owned(value)
+                    }
+                    None => Cow::Borrowed(&input[..i]),
+                };
+                return Ok((&input[i + 1..], value));
+            }
+            '\\' => {
+                if let Some((j, escaped @ ('"' | '\\'))) = chars.clone().next() {
+                    let value = unescaped.get_or_insert_with(String::new);
+                    value.push_str(&input[start..i]);
+                    value.push(escaped);
+                    start = j + 1;
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(Err::Error(DatError::from_char(&input[input.len()..], '"')))
 }
 
-fn unquoted_string(input: &str) -> IResult<&str, &str> {
-    take_till1(|c| c == ' ' || c == '\n')(input)
+pub(crate) fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r' || c == '\n'
 }
 
-fn string_key(input: &str) -> IResult<&str, &str> {
+/// Whitespace within a line that separates a key from its value.
+pub(crate) fn separator(input: &str) -> IResult<'_, &str> {
+    take_while1(|c| c == ' ' || c == '\t')(input)
+}
+
+pub(crate) fn unquoted_string(input: &str) -> IResult<'_, Cow<'_, str>> {
+    let (input, value) = take_till1(is_space)(input)?;
+    Ok((input, Cow::Borrowed(value)))
+}
+
+pub(crate) fn trim(value: Cow<'_, str>) -> Cow<'_, str> {
+    match value {
+        Cow::Borrowed(value) => Cow::Borrowed(value.trim()),
+        Cow::Owned(value) if value.trim().len() != value.len() => {
+            Cow::Owned(String::from(value.trim()))
+        }
+        value => value,
+    }
+}
+
+pub(crate) fn string_key(input: &str) -> IResult<'_, &str> {
     let (input, _) = multispace0(input)?;
-    let (input, key) = take_till1(|c| c == ' ' || c == '\n' || c == '"')(input)?;
+    let (input, key) = take_till1(|c| is_space(c) || c == '"' || c == '(' || c == ')')(input)?;
     Ok((input, key))
 }
 
-fn parse_string_value(input: &str) -> IResult<&str, (&str, ParsedValue)> {
+fn parse_string_value(input: &str) -> IResult<'_, (&str, ParsedValue<'_>)> {
     let (input, _) = multispace0(input)?;
     let (input, key) = string_key(input)?;
-    let (input, _) = char(' ')(input)?;
+    let (input, _) = separator(input)?;
     let (input, value) = alt((quoted_string, unquoted_string))(input)?;
-    Ok((input, (key, ParsedValue::Value(value.trim()))))
+    Ok((input, (key, ParsedValue::Value(trim(value)))))
 }
 
-fn parse_sub_entry(input: &str) -> IResult<&str, (&str, ParsedValue)> {
+fn parse_sub_entry(input: &str, depth: usize) -> IResult<'_, (&str, ParsedValue<'_>)> {
     let (input, _) = multispace0(input)?;
     let (input, key) = string_key(input)?;
-    let (input, _) = char(' ')(input)?;
-    let (input, contents) = subentry_contents(input)?;
+    let (input, contents) = subentry_contents(input, depth)?;
     Ok((input, (key, ParsedValue::Subentry(contents))))
 }
 
-fn parse_sub_entry_data<'a>(keys: Vec<(&'a str, ParsedValue<'a>)>) -> SubEntry<'a> {
-    let mut map = IndexMap::new();
+/// Parses a value or a sub-entry nested `depth` levels deep.
+fn parse_entry(input: &str, depth: usize) -> IResult<'_, (&str, ParsedValue<'_>)> {
+    alt((|input| parse_sub_entry(input, depth), parse_string_value))(input)
+}
+
+/// Builds the entries of a fragment or sub-entry, grouping repeated keys in the order they first appear.
+fn build_entries<'a>(
+    keys: Vec<(&'a str, ParsedValue<'a>)>,
+) -> IndexMap<Cow<'a, str>, Node<EntryData<'a>>> {
+    let mut map: IndexMap<Cow<'a, str>, Node<EntryData<'a>>> = IndexMap::new();
     for (key, value) in keys {
-        match value {
-            ParsedValue::Value(value) => {
-                if let Some(node) = map.remove(key) {
-                    match node {
-                        Node::Unique(prev) => {
-                            map.insert(key, Node::Many(vec![prev, value]));
-                        }
-                        Node::Many(mut prevs) => {
-                            prevs.push(value);
-                            map.insert(key, Node::Many(prevs));
-                        }
-                    }
-                } else {
-                    map.insert(key, Node::Unique(value));
-                }
+        let data = match value {
+            ParsedValue::Subentry(keys) => EntryData::SubEntry(SubEntry {
+                keys: build_entries(keys),
+            }),
+            ParsedValue::Value(value) => EntryData::Scalar(value),
+        };
+        match map.get_mut(key) {
+            Some(node) => node.push(data),
+            None => {
+                map.insert(Cow::Borrowed(key), Node::Unique(data));
             }
-            // Subentries can not recurse indefinitely.
-            _ => unreachable!(),
         }
     }
-    SubEntry { keys: map }
+    map
 }
 
 /// Parse multiple ListInfo entries as a document.
+///
+/// The entire input must consist of fragments, otherwise a `ParseError` is returned
+/// that points at the first fragment that failed to parse.
 pub fn parse_document<'a>(input: &'a str) -> Result<DatDocument<'a>, Error> {
-    let (_, fragments) = complete(many1(parse_fragment_internal))(input)?;
-    let mut document: IndexMap<&'a str, Vec<EntryFragment<'a>>> = IndexMap::new();
-    for (key, entry) in fragments {
+    let mut document: IndexMap<Cow<'a, str>, Vec<EntryFragment<'a>>> = IndexMap::new();
+    for fragment in fragments(input) {
+        let (key, entry) = fragment?;
         if let Some(existing) = document.get_mut(key) {
             existing.push(entry);
         } else {
-            document.insert(key, vec![entry]);
+            document.insert(Cow::Borrowed(key), vec![entry]);
         }
     }
     Ok(DatDocument { document })
 }
 
+/// Lazily parse the top level fragments of a ListInfo DAT, one at a time.
+///
+/// Unlike `parse_document`, fragments are not collected, so fragments that are
+/// not needed can be dropped while scanning large DATs. Once a fragment fails to
+/// parse, its error is yielded and iteration stops.
+///
+/// ```rust
+/// use listinfo::parse;
+///
+/// const DAT: &str = r#"clrmamepro ( name "Cave Story" )
+///     game ( name "Cave Story (En)" )
+///     game ( name "Cave Story (Jp)" )"#;
+///
+/// let mut games = 0;
+/// for fragment in parse::fragments(DAT) {
+///     let (key, _fragment) = fragment.unwrap();
+///     if key == "game" {
+///         games += 1;
+///     }
+/// }
+/// assert_eq!(games, 2);
+/// ```
+pub fn fragments(input: &str) -> Fragments<'_> {
+    Fragments {
+        input,
+        remaining: Some(skip_bom(input)),
+        yielded: false,
+    }
+}
+
+/// Iterator over the top level fragments of a ListInfo DAT.
+///
+/// This is created by `parse::fragments`.
+#[derive(Debug, Clone)]
+pub struct Fragments<'a> {
+    input: &'a str,
+    remaining: Option<&'a str>,
+    yielded: bool,
+}
+
+impl<'a> Iterator for Fragments<'a> {
+    type Item = Result<(&'a str, EntryFragment<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining.take()?;
+        let rest = match multispace0::<_, DatError<'_>>(remaining) {
+            Ok((rest, _)) => rest,
+            Err(e) => return Some(Err(to_error(e, self.input, remaining))),
+        };
+        // A document must contain at least one fragment.
+        if rest.is_empty() && self.yielded {
+            return None;
+        }
+        match parse_fragment_internal(rest) {
+            Ok((rest, fragment)) => {
+                self.remaining = Some(rest);
+                self.yielded = true;
+                Some(Ok(fragment))
+            }
+            Err(e) => Some(Err(to_error(e, self.input, rest))),
+        }
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Fragments<'a> {}
+
 /// Parse a single ListInfo entry, returning its key and the entry.
-pub fn parse_fragment(input: &str) -> Result<(&str, EntryFragment), Error> {
-    let (_, fragment) = parse_fragment_internal(input)?;
+pub fn parse_fragment(input: &str) -> Result<(&str, EntryFragment<'_>), Error> {
+    let fragment = skip_bom(input);
+    let (_, fragment) =
+        parse_fragment_internal(fragment).map_err(|e| to_error(e, input, fragment))?;
     Ok(fragment)
 }
 
-fn parse_fragment_internal(
-    input: &str,
-) -> IResult<&str, (&str, EntryFragment)> {
+/// Skips the byte order mark some Windows tools write at the start of a file.
+pub(crate) fn skip_bom(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input)
+}
+
+fn parse_fragment_internal(input: &str) -> IResult<'_, (&str, EntryFragment<'_>)> {
     let (input, _) = multispace0(input)?;
     let (input, entry_key) = string_key(input)?;
     let (input, _) = open_entry(input)?;
 
-    let mut map = IndexMap::new();
-
-    let (input, keys) = many0(alt((parse_sub_entry, parse_string_value)))(input)?;
-    for (key, value) in keys {
-        match value {
-            ParsedValue::Subentry(value) => {
-                let subentry = parse_sub_entry_data(value);
-                if let Some(node) = map.remove(key) {
-                    match node {
-                        Node::Unique(prev) => {
-                            map.insert(key, Node::Many(vec![prev, EntryData::SubEntry(subentry)]));
-                        }
-                        Node::Many(mut prevs) => {
-                            prevs.push(EntryData::SubEntry(subentry));
-                            map.insert(key, Node::Many(prevs));
-                        }
-                    }
-                } else {
-                    map.insert(key, Node::Unique(EntryData::SubEntry(subentry)));
-                }
-            }
-            ParsedValue::Value(value) => {
-                if let Some(node) = map.remove(key) {
-                    match node {
-                        Node::Unique(prev) => {
-                            map.insert(key, Node::Many(vec![prev, EntryData::Scalar(value)]));
-                        }
-                        Node::Many(mut prevs) => {
-                            prevs.push(EntryData::Scalar(value));
-                            map.insert(key, Node::Many(prevs));
-                        }
-                    }
-                } else {
-                    map.insert(key, Node::Unique(EntryData::Scalar(value)));
-                }
-            }
+    let (input, keys) = many0(|input| parse_entry(input, 1))(input)?;
+    let map = build_entries(keys);
+    let (input, _) = match close_entry(input) {
+        Ok(closed) => closed,
+        // The fragment may not be closed because an entry failed to parse,
+        // in which case the entry error is the more useful one to report.
+        Err(Err::Error(close_err)) => {
+            return Err(Err::Error(match parse_entry(input, 1) {
+                Err(Err::Error(entry_err)) => nom::error::ParseError::or(close_err, entry_err),
+                _ => close_err,
+            }))
         }
-    }
-    let (input, _) = close_entry(input)?;
-    Ok((input, (entry_key, EntryFragment::new(map))))
+        Err(err) => return Err(err),
+    };
+    Ok((input, (entry_key, EntryFragment { keys: map })))
 }
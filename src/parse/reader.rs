@@ -0,0 +1,266 @@
+//! Incremental parsing of ListInfo DATs from buffered IO streams.
+
+use nom::{
+    bytes::streaming::{take_till1, take_while1},
+    character::streaming::{char, multispace0, space0},
+    Err, Needed,
+};
+
+use std::io::{self, BufRead};
+use std::str;
+
+use super::{is_space, parse_fragment_internal, to_error, DatError, IResult, MAX_DEPTH};
+use crate::elements::*;
+use crate::error::Error;
+
+/// Reads top level fragments from a buffered IO stream, one at a time.
+///
+/// Input is read in chunks up to the next closing parenthesis, and only the fragment
+/// that is currently being parsed is kept in memory. Each chunk is scanned from the start
+/// of the entry that the previous chunk ended in. Fragments are copied out of the
+/// buffer, so they do not borrow from the reader.
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use listinfo::parse::Reader;
+///
+/// const DAT: &str = r#"clrmamepro ( name "Cave Story" )
+///     game ( name "Cave Story (En)" )"#;
+///
+/// for fragment in Reader::new(Cursor::new(DAT)) {
+///     let (key, fragment) = fragment.unwrap();
+///     if key == "game" {
+///         assert_eq!(fragment.entry_unique("name").and_then(|n| n.as_scalar()), Some("Cave Story (En)"));
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: R,
+    buffer: String,
+    chunk: Vec<u8>,
+    offset: usize,
+    line: usize,
+    column: usize,
+    scan: Scan,
+    eof: bool,
+    yielded: bool,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a reader over the given buffered IO stream.
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader,
+            buffer: String::new(),
+            chunk: Vec::new(),
+            offset: 0,
+            line: 0,
+            column: 0,
+            scan: Scan::default(),
+            eof: false,
+            yielded: false,
+            done: false,
+        }
+    }
+
+    /// Unwraps the underlying IO stream.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads input up to and including the next closing parenthesis,
+    /// returning `false` once the stream is exhausted.
+    fn fill(&mut self) -> io::Result<bool> {
+        self.chunk.clear();
+        if self.reader.read_until(b')', &mut self.chunk)? == 0 {
+            return Ok(false);
+        }
+        // Chunks end on an ASCII parenthesis, so they never split a UTF-8 sequence.
+        let chunk = str::from_utf8(&self.chunk)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if self.offset == 0 && self.buffer.is_empty() {
+            self.buffer.push_str(super::skip_bom(chunk));
+            self.offset = chunk.len() - self.buffer.len();
+            self.column = usize::from(self.offset > 0);
+        } else {
+            self.buffer.push_str(chunk);
+        }
+        Ok(true)
+    }
+
+    /// Drops the first `len` bytes of the buffer, keeping track of their position in the stream.
+    fn consume(&mut self, len: usize) {
+        let consumed = &self.buffer[..len];
+        self.offset += len;
+        match consumed.rfind('\n') {
+            Some(i) => {
+                self.line += consumed.matches('\n').count();
+                self.column = consumed[i + 1..].chars().count();
+            }
+            None => self.column += consumed.chars().count(),
+        }
+        self.buffer.drain(..len);
+        self.scan = Scan::default();
+    }
+
+    fn next_fragment(&mut self) -> Result<Option<(String, EntryFragment<'static>)>, Error> {
+        let len = loop {
+            match self.scan.fragment_boundary(&self.buffer) {
+                Ok((rest, _)) => break self.buffer.len() - rest.len(),
+                Err(Err::Incomplete(_)) if !self.eof => self.eof = !self.fill()?,
+                // Let the complete parser report the error, or that the stream ended early.
+                _ => break self.buffer.len(),
+            }
+        };
+
+        if self.yielded && self.buffer[..len].trim_start_matches(is_space).is_empty() {
+            return Ok(None);
+        }
+        let (len, fragment) = match parse_fragment_internal(&self.buffer[..len]) {
+            // Anything after the end of the parsed fragment is kept for the next one.
+            Ok((rest, (key, fragment))) => {
+                (len - rest.len(), (String::from(key), fragment.into_owned()))
+            }
+            Err(err) => {
+                return Err(match to_error(err, &self.buffer, &self.buffer) {
+                    Error::ParseError(mut err) => {
+                        err.relocate(self.offset, self.line, self.column);
+                        Error::ParseError(err)
+                    }
+                    err => err,
+                })
+            }
+        };
+        self.consume(len);
+        self.yielded = true;
+        Ok(Some(fragment))
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<(String, EntryFragment<'static>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_fragment().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+impl<R: BufRead> core::iter::FusedIterator for Reader<R> {}
+
+/// How far the fragment at the start of the buffer has been scanned, so that scanning
+/// resumes where it stopped once more input has been read.
+#[derive(Debug, Default)]
+struct Scan {
+    /// Whether the opening parenthesis of the fragment has been read.
+    opened: bool,
+    /// The end of the last complete entry of the fragment.
+    pos: usize,
+    /// Where the quoted value that ran past the end of the input starts, and how far its
+    /// closing quote has been looked for.
+    quoted: Option<(usize, usize)>,
+}
+
+impl Scan {
+    /// Finds the end of the fragment at the start of the input without building it,
+    /// returning `Incomplete` until its closing parenthesis has been read.
+    ///
+    /// Entries are recognised by the same rules as the complete parser, so a value that
+    /// starts with a parenthesis is only a sub-entry if the complete parser would read it
+    /// as one, and whether a value starting with a quote is a quoted string depends on
+    /// whether a closing quote follows anywhere before the end of the stream. Scanning
+    /// resumes from the last complete entry of the fragment.
+    fn fragment_boundary<'a>(&mut self, input: &'a str) -> IResult<'a, ()> {
+        if !self.opened {
+            let (rest, _) = multispace0(input)?;
+            let (rest, _) = streaming_key(rest)?;
+            let (rest, _) = multispace0(rest)?;
+            let (rest, _) = char('(')(rest)?;
+            self.opened = true;
+            self.pos = input.len() - rest.len();
+        }
+        loop {
+            match self.entry(input, &input[self.pos..], 1) {
+                Ok((rest, _)) => self.pos = input.len() - rest.len(),
+                Err(Err::Error(_)) => {
+                    let (rest, _) = multispace0(&input[self.pos..])?;
+                    let (rest, _) = char(')')(rest)?;
+                    return Ok((rest, ()));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Recognises a value or a sub-entry nested `depth` levels deep, like `parse_entry`.
+    fn entry<'a>(&mut self, input: &'a str, rest: &'a str, depth: usize) -> IResult<'a, ()> {
+        match self.sub_entry(input, rest, depth) {
+            Err(Err::Error(_)) => self.scalar(input, rest),
+            result => result,
+        }
+    }
+
+    fn sub_entry<'a>(&mut self, input: &'a str, rest: &'a str, depth: usize) -> IResult<'a, ()> {
+        let (rest, _) = multispace0(rest)?;
+        let (rest, _) = streaming_key(rest)?;
+        let (rest, _) = space0(rest)?;
+        let (body, _) = char('(')(rest)?;
+        if depth > MAX_DEPTH {
+            return Err(Err::Failure(DatError::too_deep(rest)));
+        }
+        let (mut rest, _) = self.entry(input, body, depth + 1)?;
+        loop {
+            match self.entry(input, rest, depth + 1) {
+                Ok((next, _)) => rest = next,
+                Err(Err::Error(_)) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        let (rest, _) = multispace0(rest)?;
+        let (rest, _) = char(')')(rest)?;
+        Ok((rest, ()))
+    }
+
+    fn scalar<'a>(&mut self, input: &'a str, rest: &'a str) -> IResult<'a, ()> {
+        let (rest, _) = multispace0(rest)?;
+        let (rest, _) = streaming_key(rest)?;
+        let (rest, _) = take_while1(|c| c == ' ' || c == '\t')(rest)?;
+        match self.quoted_value(input, rest) {
+            Err(Err::Error(_)) => take_till1(is_space)(rest).map(|(rest, _)| (rest, ())),
+            result => result,
+        }
+    }
+
+    /// Recognises a quoted value, continuing the search for its closing quote from where
+    /// it stopped if the value ran past the end of the input before.
+    fn quoted_value<'a>(&mut self, input: &'a str, rest: &'a str) -> IResult<'a, ()> {
+        let (value, _) = char('"')(rest)?;
+        let start = input.len() - value.len();
+        let searched = match self.quoted {
+            Some((quoted, searched)) if quoted == start => searched,
+            _ => 0,
+        };
+        let mut resume = value.len();
+        let mut chars = value[searched..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((&value[searched + i + 1..], ())),
+                // Look at the escaped character again once it has been read.
+                '\\' if chars.next().is_none() => resume = searched + i,
+                _ => {}
+            }
+        }
+        self.quoted = Some((start, resume));
+        Err(Err::Incomplete(Needed::Unknown))
+    }
+}
+
+fn streaming_key(input: &str) -> IResult<'_, &str> {
+    take_till1(|c| is_space(c) || c == '"' || c == '(' || c == ')')(input)
+}
@@ -0,0 +1,197 @@
+//! Reading and writing RomCenter DAT files.
+//!
+//! RomCenter DATs are INI-like files with `[CREDITS]`, `[DAT]` and `[EMULATOR]` sections of
+//! `key=value` lines, followed by a `[GAMES]` section with one line per ROM, whose fields are
+//! separated by `¬`.
+//!
+//! ```text
+//! ¬parent name¬parent description¬game name¬game description¬rom name¬rom crc¬rom size¬romof name¬merge name¬
+//! ```
+//!
+//! The values of `[CREDITS]` are read as values of a `clrmamepro` header, with the `refname`
+//! and `version` of `[EMULATOR]` read as its `name` and `description`, and the values of `[DAT]`
+//! read into its `romcenter` sub-entry. The lines of a set are read as a `game` fragment with its
+//! `name` and `description`, a `cloneof` if its parent is another set, its `romof`, and a `rom`
+//! sub-entry for every line with a ROM. Sets in a `[RESOURCES]` section are read as `resource`
+//! fragments, and other sections are ignored.
+//!
+//! RomCenter DATs are usually encoded as Windows-1252, and must be decoded before parsing.
+//!
+//! ```rust
+//! use listinfo::romcenter;
+//!
+//! const DAT: &str = "[CREDITS]
+//! author=Logiqx
+//! [DAT]
+//! version=2.50
+//! [EMULATOR]
+//! refname=MAME
+//! version=MAME 0.78
+//! [GAMES]
+//! ¬pacman¬Pac-Man¬puckman¬PuckMan¬pacman.6e¬c1e6ab10¬4096¬pacman¬pacman.6e¬
+//! ¬pacman¬Pac-Man¬puckman¬PuckMan¬puckman.6f¬1a6fb2d4¬4096¬¬¬
+//! ";
+//!
+//! let document = romcenter::parse_document(DAT).unwrap();
+//! let header = document.entry("clrmamepro").unwrap().next().unwrap();
+//! assert_eq!(header.entry_unique("name").and_then(|name| name.as_scalar()), Some("MAME"));
+//!
+//! let game = document.entry("game").unwrap().next().unwrap();
+//! assert_eq!(game.entry_unique("cloneof").and_then(|cloneof| cloneof.as_scalar()), Some("pacman"));
+//! assert_eq!(game.entry("rom").unwrap().iter().count(), 2);
+//! ```
+//!
+//! Documents can be written back out as RomCenter DATs with [`write_document`](fn.write_document.html)
+//! or [`to_string`](fn.to_string.html), whatever format they were read from, as long as they
+//! only have values that RomCenter DATs can represent.
+
+use alloc::vec::Vec;
+use indexmap::IndexMap;
+
+use crate::elements::*;
+use crate::error::{Error, ParseError};
+
+mod write;
+
+pub use write::{to_string, write_document};
+
+/// The separator between the fields of a line of a set.
+const SEPARATOR: char = '¬';
+
+#[derive(Clone, Copy)]
+enum Section {
+    Credits,
+    Dat,
+    Emulator,
+    Sets(&'static str),
+    Other,
+}
+
+impl Section {
+    fn new(name: &str) -> Self {
+        let sections = [
+            ("CREDITS", Section::Credits),
+            ("DAT", Section::Dat),
+            ("EMULATOR", Section::Emulator),
+            ("GAMES", Section::Sets("game")),
+            ("RESOURCES", Section::Sets("resource")),
+        ];
+        sections
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map_or(Section::Other, |&(_, section)| section)
+    }
+}
+
+type Sets<'a> = IndexMap<(&'static str, &'a str), EntryFragment<'a>>;
+
+/// Parses a RomCenter DAT.
+///
+/// Lines of the same set are read into a single fragment, even if they are not consecutive.
+pub fn parse_document(input: &str) -> Result<DatDocument<'_>, Error> {
+    let mut header = EntryFragment::new();
+    let mut romcenter = SubEntry::new();
+    let mut sets = Sets::new();
+    let mut section = Section::Other;
+
+    for line in input.split('\n') {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| error(input, line, line.len(), "`]`", None))?;
+            section = Section::new(name.trim());
+            continue;
+        }
+
+        let (key, value) = match section {
+            Section::Sets(key) => {
+                read_set(input, line, key, &mut sets)?;
+                continue;
+            }
+            Section::Other => continue,
+            _ => line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| error(input, line, line.len(), "`=`", Some("clrmamepro")))?,
+        };
+        match section {
+            Section::Dat => romcenter.push(key, value),
+            Section::Emulator if key.eq_ignore_ascii_case("refname") => header.push("name", value),
+            Section::Emulator if key.eq_ignore_ascii_case("version") => {
+                header.push("description", value)
+            }
+            _ => header.push(key, value),
+        }
+    }
+
+    let mut document = DatDocument::new();
+    if !romcenter.keys.is_empty() {
+        header.push("romcenter", romcenter);
+    }
+    if !header.keys.is_empty() {
+        document.push("clrmamepro", header);
+    }
+    for ((key, _), set) in sets {
+        document.push(key, set);
+    }
+    Ok(document)
+}
+
+/// Reads a line of a set into the fragment of the set.
+fn read_set<'a>(
+    input: &'a str,
+    line: &'a str,
+    key: &'static str,
+    sets: &mut Sets<'a>,
+) -> Result<(), Error> {
+    let fields = line
+        .strip_prefix(SEPARATOR)
+        .ok_or_else(|| error(input, line, 0, "`¬`", Some(key)))?;
+    let fields: Vec<&str> = fields.split(SEPARATOR).collect();
+    let (parent, name, description, rom, crc, size, romof, merge) = match fields[..] {
+        [parent, _, name, description, rom, crc, size, romof, merge, ..] => {
+            (parent, name, description, rom, crc, size, romof, merge)
+        }
+        _ => return Err(error(input, line, line.len(), "`¬`", Some(key))),
+    };
+    if name.is_empty() {
+        let offset = name.as_ptr() as usize - line.as_ptr() as usize;
+        return Err(error(input, line, offset, "a set name", Some(key)));
+    }
+
+    let set = sets.entry((key, name)).or_insert_with(|| {
+        let mut set = EntryFragment::new();
+        set.push("name", name);
+        if !description.is_empty() {
+            set.push("description", description);
+        }
+        if !parent.is_empty() && parent != name {
+            set.push("cloneof", parent);
+        }
+        if !romof.is_empty() {
+            set.push("romof", romof);
+        }
+        set
+    });
+    if !rom.is_empty() {
+        let mut entry = SubEntry::new();
+        entry.push("name", rom);
+        for &(key, value) in [("merge", merge), ("size", size), ("crc", crc)].iter() {
+            if !value.is_empty() {
+                entry.push(key, value);
+            }
+        }
+        set.push("rom", entry);
+    }
+    Ok(())
+}
+
+/// Creates an error at the given byte offset into a line of the input.
+fn error(input: &str, line: &str, offset: usize, expected: &str, fragment: Option<&str>) -> Error {
+    let offset = line.as_ptr() as usize - input.as_ptr() as usize + offset;
+    ParseError::new(input, offset, expected.into(), fragment, None).into()
+}
@@ -0,0 +1,242 @@
+//! Writing documents as RomCenter DAT files.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use indexmap::IndexMap;
+
+use super::SEPARATOR;
+use crate::elements::*;
+use crate::write::WriteOptions;
+
+/// The version of the RomCenter format that is written when the header does not name one.
+const FORMAT_VERSION: &str = "2.50";
+
+/// The fragment keys of the sets written to the `[GAMES]` section.
+const GAME_KEYS: [&str; 2] = ["game", "machine"];
+
+/// The fragment keys of the sets written to the `[RESOURCES]` section.
+const RESOURCE_KEYS: [&str; 1] = ["resource"];
+
+/// The values of a set that are written to its lines.
+const SET_VALUES: [&str; 5] = ["name", "description", "cloneof", "romof", "rom"];
+
+/// The values of a `rom` sub-entry that are written to its line.
+const ROM_VALUES: [&str; 4] = ["name", "crc", "size", "merge"];
+
+type Keys<'a> = IndexMap<Cow<'a, str>, Node<EntryData<'a>>>;
+
+/// Writes the document as a RomCenter DAT.
+///
+/// The `name` and `description` of the `clrmamepro` header are written as the `refname` and
+/// `version` of `[EMULATOR]`, the values of its `romcenter` sub-entry are written to `[DAT]`,
+/// and its other values to `[CREDITS]`. `game` and `machine` fragments are written to
+/// `[GAMES]`, and `resource` fragments to `[RESOURCES]`, with a line for every `rom` of a set,
+/// or a line without a ROM if the set has none.
+///
+/// RomCenter DATs can only represent the `name`, `description`, `cloneof` and `romof` of a
+/// set, the `name`, `crc`, `size` and `merge` of its ROMs, and a single header with scalar
+/// values. Writing fails with `fmt::Error` if the document has anything else, such as a
+/// disk, a set without a name or a second `description`, or a value that contains a line
+/// break, or `¬` within the lines of a set. Such values can be removed first with
+/// [`EntryFragment::retain`](../struct.EntryFragment.html#method.retain) to write the rest
+/// of the document. RomCenter itself writes `\r\n` line endings, which can be set in the
+/// options. Only the line endings of the options are used.
+pub fn write_document<W: Write>(
+    document: &DatDocument<'_>,
+    w: &mut W,
+    options: &WriteOptions,
+) -> fmt::Result {
+    let known =
+        |key: &str| key == "clrmamepro" || GAME_KEYS.contains(&key) || RESOURCE_KEYS.contains(&key);
+    if !document.document.keys().all(|key| known(key)) {
+        return Err(fmt::Error);
+    }
+    let header = match document.document.get("clrmamepro").map(Vec::as_slice) {
+        None => None,
+        Some([header]) => Some(header),
+        Some(_) => return Err(fmt::Error),
+    };
+
+    write_section(w, options, "CREDITS")?;
+    if let Some(header) = header {
+        for (key, node) in header.keys.iter() {
+            if key == "name" || key == "description" || key == "romcenter" {
+                continue;
+            }
+            for value in node.iter() {
+                write_value(w, options, key, value.as_scalar().ok_or(fmt::Error)?)?;
+            }
+        }
+    }
+
+    write_section(w, options, "DAT")?;
+    let romcenter = match header.and_then(|header| header.keys.get("romcenter")) {
+        None => None,
+        Some(Node::Unique(EntryData::SubEntry(romcenter))) => Some(romcenter),
+        Some(_) => return Err(fmt::Error),
+    };
+    match romcenter {
+        Some(romcenter) => {
+            if !romcenter.keys.contains_key("version") {
+                write_value(w, options, "version", FORMAT_VERSION)?;
+            }
+            for (key, node) in romcenter.keys.iter() {
+                for value in node.iter() {
+                    write_value(w, options, key, value.as_scalar().ok_or(fmt::Error)?)?;
+                }
+            }
+        }
+        None => write_value(w, options, "version", FORMAT_VERSION)?,
+    }
+
+    write_section(w, options, "EMULATOR")?;
+    if let Some(header) = header {
+        for &(key, name) in [("name", "refname"), ("description", "version")].iter() {
+            if let Some(value) = single(&header.keys, key)? {
+                write_value(w, options, name, value)?;
+            }
+        }
+    }
+
+    let mut descriptions = IndexMap::new();
+    for key in GAME_KEYS.iter().chain(RESOURCE_KEYS.iter()) {
+        for set in document.document.get(*key).into_iter().flatten() {
+            if let Some(name) = single(&set.keys, "name")? {
+                let description = single(&set.keys, "description")?.unwrap_or("");
+                descriptions.entry(name).or_insert(description);
+            }
+        }
+    }
+    write_section(w, options, "GAMES")?;
+    write_sets(document, w, options, &GAME_KEYS, &descriptions)?;
+    if RESOURCE_KEYS
+        .iter()
+        .any(|key| document.document.contains_key(*key))
+    {
+        write_section(w, options, "RESOURCES")?;
+        write_sets(document, w, options, &RESOURCE_KEYS, &descriptions)?;
+    }
+    Ok(())
+}
+
+/// Writes the document as a RomCenter DAT with the default options.
+///
+/// Fails if the document has a value that can not be written, as `write_document` does.
+pub fn to_string(document: &DatDocument<'_>) -> Result<String, fmt::Error> {
+    let mut dat = String::new();
+    write_document(document, &mut dat, &WriteOptions::default())?;
+    Ok(dat)
+}
+
+fn write_section<W: Write>(w: &mut W, options: &WriteOptions, name: &str) -> fmt::Result {
+    write!(w, "[{}]", name)?;
+    options.write_newline(w)
+}
+
+fn write_value<W: Write>(w: &mut W, options: &WriteOptions, key: &str, value: &str) -> fmt::Result {
+    if has_line_break(key) || has_line_break(value) {
+        return Err(fmt::Error);
+    }
+    write!(w, "{}={}", key, value)?;
+    options.write_newline(w)
+}
+
+/// Writes the lines of the sets with the given keys, given the description of every set by name.
+fn write_sets<W: Write>(
+    document: &DatDocument<'_>,
+    w: &mut W,
+    options: &WriteOptions,
+    keys: &[&str],
+    descriptions: &IndexMap<&str, &str>,
+) -> fmt::Result {
+    for key in keys {
+        for set in document.document.get(*key).into_iter().flatten() {
+            if !has_only(&set.keys, &SET_VALUES) {
+                return Err(fmt::Error);
+            }
+            let name = single(&set.keys, "name")?.ok_or(fmt::Error)?;
+            let parent = single(&set.keys, "cloneof")?.unwrap_or(name);
+            let parent_description = descriptions.get(parent).copied().unwrap_or("");
+            let description = single(&set.keys, "description")?.unwrap_or("");
+            let romof = single(&set.keys, "romof")?.unwrap_or("");
+
+            let roms = set
+                .keys
+                .get("rom")
+                .map(Node::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|rom| match rom {
+                    EntryData::SubEntry(rom) if has_only(&rom.keys, &ROM_VALUES) => Ok(rom),
+                    _ => Err(fmt::Error),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if roms.is_empty() {
+                let fields = [
+                    parent,
+                    parent_description,
+                    name,
+                    description,
+                    "",
+                    "",
+                    "",
+                    romof,
+                    "",
+                ];
+                write_line(w, options, &fields)?;
+            }
+            for rom in roms {
+                let value = |key| single(&rom.keys, key).map(Option::unwrap_or_default);
+                let fields = [
+                    parent,
+                    parent_description,
+                    name,
+                    description,
+                    single(&rom.keys, "name")?.ok_or(fmt::Error)?,
+                    value("crc")?,
+                    value("size")?,
+                    romof,
+                    value("merge")?,
+                ];
+                write_line(w, options, &fields)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_line<W: Write>(w: &mut W, options: &WriteOptions, fields: &[&str; 9]) -> fmt::Result {
+    if fields
+        .iter()
+        .any(|field| field.contains(SEPARATOR) || has_line_break(field))
+    {
+        return Err(fmt::Error);
+    }
+    for field in fields {
+        w.write_char(SEPARATOR)?;
+        w.write_str(field)?;
+    }
+    w.write_char(SEPARATOR)?;
+    options.write_newline(w)
+}
+
+fn has_line_break(value: &str) -> bool {
+    value.contains(['\r', '\n'])
+}
+
+/// Gets a value that can only be written once, failing if it is repeated or is a sub-entry.
+fn single<'e>(keys: &'e Keys<'_>, key: &str) -> Result<Option<&'e str>, fmt::Error> {
+    match keys.get(key) {
+        None => Ok(None),
+        Some(Node::Unique(EntryData::Scalar(value))) => Ok(Some(value)),
+        Some(_) => Err(fmt::Error),
+    }
+}
+
+/// Whether the entries only have values with the given keys.
+fn has_only(keys: &Keys<'_>, values: &[&str]) -> bool {
+    keys.keys().all(|key| values.contains(&&**key))
+}
@@ -0,0 +1,300 @@
+use super::value::{Level, MapKeySerializer, ValueSerializer};
+use crate::Error;
+
+use alloc::string::{String, ToString};
+use core::fmt::{Arguments, Write};
+use core::result::Result as CoreResult;
+use serde::ser::{Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+
+type Result<T> = CoreResult<T, Error>;
+
+/// A serializer for a ListInfo DAT.
+pub struct DatDocumentSerializer<W> {
+    writer: W,
+    fragments: usize,
+    /// The openings of sub-entries that have no values yet, which are only written once
+    /// they do, since a sub-entry without values can not be parsed.
+    pending: String,
+}
+
+impl<W: Write> DatDocumentSerializer<W> {
+    /// Creates a DAT serializer that writes into the given sink.
+    pub fn new(writer: W) -> Self {
+        DatDocumentSerializer {
+            writer,
+            fragments: 0,
+            pending: String::new(),
+        }
+    }
+
+    /// Unwraps the underlying sink.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub(crate) fn write_str(&mut self, s: &str) -> Result<()> {
+        self.write_pending()?;
+        self.writer.write_str(s).map_err(write_error)
+    }
+
+    pub(crate) fn write_fmt(&mut self, args: Arguments<'_>) -> Result<()> {
+        self.write_pending()?;
+        self.writer.write_fmt(args).map_err(write_error)
+    }
+
+    fn write_pending(&mut self) -> Result<()> {
+        if !self.pending.is_empty() {
+            self.writer.write_str(&self.pending).map_err(write_error)?;
+            self.pending.clear();
+        }
+        Ok(())
+    }
+
+    /// Holds the opening of a sub-entry until a value is written into it, returning the
+    /// mark to close it with.
+    pub(crate) fn open_sub_entry(&mut self, args: Arguments<'_>) -> Result<usize> {
+        let mark = self.pending.len();
+        self.pending.write_fmt(args).map_err(write_error)?;
+        Ok(mark)
+    }
+
+    /// Closes a sub-entry, or drops its opening if nothing was written into it.
+    pub(crate) fn close_sub_entry(&mut self, mark: usize, close: &str) -> Result<()> {
+        if self.pending.len() > mark {
+            self.pending.truncate(mark);
+            Ok(())
+        } else {
+            self.write_str(close)
+        }
+    }
+
+    /// Writes the separator between top level fragments.
+    pub(crate) fn begin_fragment(&mut self) -> Result<()> {
+        if self.fragments > 0 {
+            self.write_str("\n")?;
+        }
+        self.fragments += 1;
+        Ok(())
+    }
+}
+
+fn write_error(_: core::fmt::Error) -> Error {
+    Error::SerdeError("failed to write ListInfo output".to_string())
+}
+
+fn expected_document() -> Error {
+    Error::SerdeError("a ListInfo document can only be serialized from a struct or map".to_string())
+}
+
+/// Serializes the fields of the top level structure as fragments.
+pub struct DocumentFieldSerializer<'a, W> {
+    ser: &'a mut DatDocumentSerializer<W>,
+    key: Option<String>,
+}
+
+impl<'a, W: Write> SerializeStruct for DocumentFieldSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(ValueSerializer::new(self.ser, key, Level::Fragment))
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> SerializeMap for DocumentFieldSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.key.take() {
+            Some(key) => value.serialize(ValueSerializer::new(self.ser, &key, Level::Fragment)),
+            None => Err(Error::SerdeError("key is missing".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Serializer for &'a mut DatDocumentSerializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = DocumentFieldSerializer<'a, W>;
+    type SerializeStruct = DocumentFieldSerializer<'a, W>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(DocumentFieldSerializer {
+            ser: self,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(expected_document())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(expected_document())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(expected_document())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(expected_document())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(expected_document())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(expected_document())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(expected_document())
+    }
+}
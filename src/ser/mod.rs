@@ -0,0 +1,124 @@
+//! Serde `Serializer` implementation.
+//!
+//! listinfo-rs supports writing Rust data structures back out as ListInfo DAT text with serde.
+//! This must first be enabled in Cargo.toml
+//!
+//! ```toml
+//! listinfo = { version = "0.4", features = ["serialize"] }
+//! ```
+//!
+//! ## Usage
+//! The fields of the top level structure are written as fragments, and sequences of structures
+//! as repeated fragments. Within a fragment, sequences are written as repeated keys, nested
+//! structures as sub-entries such as `rom ( ... )`, and byte buffers as lowercase hex strings.
+//!
+//! An empty sequence writes no keys at all, and `None` is skipped the same way, as is a sub-entry
+//! whose fields all write nothing. To read such a DAT back, sequences and structures that may be
+//! empty need `#[serde(default)]`, otherwise deserializing fails with a missing field error.
+//!
+//! ```rust
+//! use serde::Serialize;
+//!
+//! use listinfo::ser::to_string;
+//!
+//! #[derive(Debug, Serialize)]
+//! struct Header {
+//!     name: String,
+//!     version: String,
+//! }
+//!
+//! #[derive(Debug, Serialize)]
+//! struct Game {
+//!     name: String,
+//!     rom: Vec<Rom>,
+//! }
+//!
+//! #[derive(Debug, Serialize)]
+//! struct Rom {
+//!     name: String,
+//!     size: u64,
+//!     // Supports serializing byte arrays to hex strings
+//!     #[serde(with = "serde_bytes")]
+//!     crc: Vec<u8>,
+//! }
+//!
+//! #[derive(Debug, Serialize)]
+//! struct CaveStory {
+//!     clrmamepro: Header,
+//!     game: Vec<Game>,
+//! }
+//!
+//! let cave_story = CaveStory {
+//!     clrmamepro: Header {
+//!         name: "Cave Story".into(),
+//!         version: "20161204".into(),
+//!     },
+//!     game: vec![Game {
+//!         name: "Cave Story (En)".into(),
+//!         rom: vec![Rom {
+//!             name: "Doukutsu.exe".into(),
+//!             size: 1478656,
+//!             crc: vec![0xc5, 0xa2, 0xa3, 0xf6],
+//!         }],
+//!     }],
+//! };
+//!
+//! let dat = to_string(&cave_story).unwrap();
+//! assert!(dat.contains(r#"rom ( name "Doukutsu.exe" size 1478656 crc c5a2a3f6 )"#));
+//! ```
+mod dat_document;
+mod value;
+
+#[cfg(all(test, feature = "test_serialize"))]
+mod tests;
+
+use alloc::string::String;
+use core::fmt::Write;
+use serde::ser::Serialize;
+
+pub use dat_document::DatDocumentSerializer as Serializer;
+
+type Result<T> = core::result::Result<T, crate::Error>;
+
+/// Serialize the given data structure as ListInfo DAT text into a `fmt::Write` sink.
+pub fn to_fmt<W: Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serialize the given data structure as a string of ListInfo DAT text.
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String> {
+    let mut output = String::new();
+    to_fmt(&mut output, value)?;
+    Ok(output)
+}
+
+/// Serialize the given data structure as ListInfo DAT text into an IO stream.
+#[cfg(feature = "std")]
+pub fn to_writer<W: std::io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
+    let mut adapter = IoWriter {
+        writer,
+        error: None,
+    };
+    let result = to_fmt(&mut adapter, value);
+    match adapter.error {
+        Some(err) => Err(crate::Error::IoError(err)),
+        None => result,
+    }
+}
+
+/// Adapts an `io::Write` into a `fmt::Write`, holding on to the underlying IO error.
+#[cfg(feature = "std")]
+struct IoWriter<W> {
+    writer: W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            core::fmt::Error
+        })
+    }
+}
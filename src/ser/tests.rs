@@ -0,0 +1,279 @@
+#![cfg(feature = "test_serialize")]
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Header {
+    name: String,
+    description: String,
+    version: String,
+    comment: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Game {
+    name: String,
+    releaseyear: u32,
+    developer: String,
+    rom: Vec<Rom>,
+    sample: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Rom {
+    name: String,
+    size: u64,
+    #[serde(with = "serde_bytes")]
+    crc: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    md5: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    sha1: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CaveStory {
+    clrmamepro: Header,
+    game: Vec<Game>,
+}
+
+fn cave_story() -> CaveStory {
+    CaveStory {
+        clrmamepro: Header {
+            name: "Cave Story".into(),
+            description: "Cave Story".into(),
+            version: "20161204".into(),
+            comment: "libretro | www.libretro.com".into(),
+        },
+        game: vec![
+            Game {
+                name: "Cave Story (En)".into(),
+                releaseyear: 2004,
+                developer: "Studio Pixel".into(),
+                rom: vec![Rom {
+                    name: "Doukutsu.exe".into(),
+                    size: 1478656,
+                    crc: vec![0xc5, 0xa2, 0xa3, 0xf6],
+                    md5: hex::decode("38695d3d69d7a0ada8178072dad4c58b").unwrap(),
+                    sha1: hex::decode("bb2d0441e073da9c584f23c2ad8c7ab8aac293bf").unwrap(),
+                }],
+                sample: vec!["sample1".into(), "sample2".into()],
+            },
+            Game {
+                name: "Cave Story \"Doukutsu Monogatari\" (Jp)".into(),
+                releaseyear: 2004,
+                developer: "Studio Pixel".into(),
+                rom: vec![
+                    Rom {
+                        name: "Doukutsu.exe".into(),
+                        size: 1478656,
+                        crc: vec![0xc5, 0xa2, 0xa3, 0xf6],
+                        md5: hex::decode("38695d3d69d7a0ada8178072dad4c58b").unwrap(),
+                        sha1: hex::decode("bb2d0441e073da9c584f23c2ad8c7ab8aac293bf").unwrap(),
+                    },
+                    Rom {
+                        name: "data.pak".into(),
+                        size: 42,
+                        crc: vec![0x00, 0x11, 0x22, 0x33],
+                        md5: hex::decode("00000000000000000000000000000000").unwrap(),
+                        sha1: hex::decode("0000000000000000000000000000000000000000").unwrap(),
+                    },
+                ],
+                sample: vec!["sample1".into(), "sample2".into()],
+            },
+        ],
+    }
+}
+
+#[test]
+fn serialize_cave_story() {
+    let dat = super::to_string(&cave_story()).unwrap();
+    assert!(dat.starts_with("clrmamepro (\n\tname \"Cave Story\"\n"));
+    assert!(dat.contains("\treleaseyear 2004\n"));
+    assert!(dat.contains("\tsample \"sample1\"\n\tsample \"sample2\"\n"));
+    assert!(dat.contains(
+        "\trom ( name \"Doukutsu.exe\" size 1478656 crc c5a2a3f6 \
+         md5 38695d3d69d7a0ada8178072dad4c58b sha1 bb2d0441e073da9c584f23c2ad8c7ab8aac293bf )\n"
+    ));
+}
+
+#[test]
+fn serialize_round_trip() {
+    let cave_story = cave_story();
+    let dat = super::to_string(&cave_story).unwrap();
+    let parsed = crate::de::from_str::<CaveStory>(&dat).unwrap();
+    assert_eq!(parsed, cave_story);
+}
+
+#[test]
+fn serialize_round_trip_empty_sequences() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Game {
+        name: String,
+        #[serde(default)]
+        rom: Vec<Rom>,
+        #[serde(default)]
+        sample: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Document {
+        game: Vec<Game>,
+    }
+
+    let document = Document {
+        game: vec![Game {
+            name: "No ROMs".into(),
+            rom: Vec::new(),
+            sample: Vec::new(),
+        }],
+    };
+    let dat = super::to_string(&document).unwrap();
+    assert_eq!(dat, "game (\n\tname \"No ROMs\"\n)\n");
+    assert_eq!(crate::de::from_str::<Document>(&dat).unwrap(), document);
+}
+
+#[test]
+fn serialize_round_trip_blank_sub_entries() {
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Status {
+        good: Option<bool>,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Rom {
+        name: Option<String>,
+        size: Option<u64>,
+        #[serde(default)]
+        status: Status,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Game {
+        name: String,
+        #[serde(default)]
+        rom: Rom,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Document {
+        game: Vec<Game>,
+    }
+
+    let document = Document {
+        game: vec![
+            Game {
+                name: "Blank".into(),
+                rom: Rom::default(),
+            },
+            Game {
+                name: "Sized".into(),
+                rom: Rom {
+                    name: None,
+                    size: Some(1),
+                    status: Status::default(),
+                },
+            },
+        ],
+    };
+    let dat = super::to_string(&document).unwrap();
+    assert_eq!(
+        dat,
+        "game (\n\tname \"Blank\"\n)\n\ngame (\n\tname \"Sized\"\n\trom ( size 1 )\n)\n"
+    );
+    assert_eq!(crate::de::from_str::<Document>(&dat).unwrap(), document);
+}
+
+#[test]
+fn serialize_to_writer() {
+    let mut buf = Vec::new();
+    super::to_writer(&mut buf, &cave_story()).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        super::to_string(&cave_story()).unwrap()
+    );
+}
+
+#[test]
+fn serialize_nested_sub_entries() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Emulation {
+        state: String,
+        depth: u8,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Driver {
+        status: String,
+        emulation: Emulation,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Game {
+        name: String,
+        driver: Driver,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Document {
+        game: Game,
+    }
+
+    let document = Document {
+        game: Game {
+            name: "Nested".into(),
+            driver: Driver {
+                status: "good".into(),
+                emulation: Emulation {
+                    state: "good".into(),
+                    depth: 8,
+                },
+            },
+        },
+    };
+    let dat = super::to_string(&document).unwrap();
+    assert_eq!(
+        dat,
+        "game (\n\tname \"Nested\"\n\tdriver ( status \"good\" emulation ( state \"good\" depth 8 ) )\n)\n"
+    );
+    assert_eq!(crate::de::from_str::<Document>(&dat).unwrap(), document);
+}
+
+#[test]
+fn serialize_hashes() {
+    use crate::hash::{Crc32, Md5};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Rom {
+        name: String,
+        crc: Crc32,
+        md5: Option<Md5>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Game {
+        rom: Rom,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Document {
+        game: Game,
+    }
+
+    let document = Document {
+        game: Game {
+            rom: Rom {
+                name: "a.bin".into(),
+                crc: Crc32::from(0xc5a2a3f6),
+                md5: "38695D3D69D7A0ADA8178072DAD4C58B".parse().ok(),
+            },
+        },
+    };
+    let dat = super::to_string(&document).unwrap();
+    assert_eq!(
+        dat,
+        "game (\n\trom ( name \"a.bin\" crc c5a2a3f6 md5 38695d3d69d7a0ada8178072dad4c58b )\n)\n"
+    );
+    assert_eq!(crate::de::from_str::<Document>(&dat).unwrap(), document);
+}
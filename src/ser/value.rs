@@ -0,0 +1,579 @@
+use super::Serializer as DatDocumentSerializer;
+use crate::write::{needs_quotes, Escaped};
+use crate::Error;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Formatter, Write};
+use core::result::Result as CoreResult;
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple,
+    SerializeTupleStruct,
+};
+
+type Result<T> = CoreResult<T, Error>;
+
+/// The position in the ListInfo DAT that a value is written to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Level {
+    /// A top level fragment, such as `game ( ... )`.
+    Fragment,
+    /// An item of a fragment, written on its own line.
+    Entry,
+    /// An item of a sub-entry, written inline such as `rom ( name "Rom 1" )`.
+    ///
+    /// Nested sub-entries are written inline within their parent.
+    SubEntry,
+}
+
+/// Displays a byte buffer as a lowercase hex string.
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> Display for Hex<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes a value under the given key.
+pub(crate) struct ValueSerializer<'a, W> {
+    ser: &'a mut DatDocumentSerializer<W>,
+    key: &'a str,
+    level: Level,
+}
+
+impl<'a, W: Write> ValueSerializer<'a, W> {
+    pub(crate) fn new(ser: &'a mut DatDocumentSerializer<W>, key: &'a str, level: Level) -> Self {
+        ValueSerializer { ser, key, level }
+    }
+
+    fn scalar<D: Display>(self, value: D) -> Result<()> {
+        match self.level {
+            Level::Fragment => Err(Error::SerdeError(format!(
+                "top level key {} must be a struct or map to be written as a fragment",
+                self.key
+            ))),
+            Level::Entry => self
+                .ser
+                .write_fmt(format_args!("\t{} {}\n", self.key, value)),
+            Level::SubEntry => self.ser.write_fmt(format_args!(" {} {}", self.key, value)),
+        }
+    }
+
+    fn quoted(self, value: &str) -> Result<()> {
+        self.scalar(format_args!("\"{}\"", Escaped(value)))
+    }
+
+    fn entries(self) -> Result<EntriesSerializer<'a, W>> {
+        let (level, mark) = match self.level {
+            Level::Fragment => {
+                self.ser.begin_fragment()?;
+                self.ser.write_fmt(format_args!("{} (\n", self.key))?;
+                (Level::Entry, None)
+            }
+            Level::Entry => {
+                let mark = self.ser.open_sub_entry(format_args!("\t{} (", self.key))?;
+                (Level::SubEntry, Some(mark))
+            }
+            Level::SubEntry => {
+                let mark = self.ser.open_sub_entry(format_args!(" {} (", self.key))?;
+                (Level::SubEntry, Some(mark))
+            }
+        };
+        Ok(EntriesSerializer {
+            ser: self.ser,
+            parent: self.level,
+            level,
+            mark,
+            key: None,
+        })
+    }
+
+    fn unsupported(self, kind: &str) -> Error {
+        Error::SerdeError(format!(
+            "{} can not be serialized as the value of key {}",
+            kind, self.key
+        ))
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for ValueSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = RepeatSerializer<'a, W>;
+    type SerializeTuple = RepeatSerializer<'a, W>;
+    type SerializeTupleStruct = RepeatSerializer<'a, W>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = EntriesSerializer<'a, W>;
+    type SerializeStruct = EntriesSerializer<'a, W>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.scalar(if v { "yes" } else { "no" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.scalar(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        let mut buf = [0; 4];
+        self.quoted(v.encode_utf8(&mut buf))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.quoted(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.scalar(Hex(v))
+    }
+
+    /// Writes the displayed value unquoted when it does not need quotes, such as hashes.
+    fn collect_str<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Display,
+    {
+        let value = value.to_string();
+        if needs_quotes(&value) {
+            self.quoted(&value)
+        } else {
+            self.scalar(value)
+        }
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.scalar(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(self.unsupported("newtype variant"))
+    }
+
+    // Elements are written as repeated keys, so an empty sequence writes nothing and can only be
+    // read back into a field with `#[serde(default)]`.
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(RepeatSerializer {
+            ser: self.ser,
+            key: self.key,
+            level: self.level,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(self.unsupported("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.entries()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.entries()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(self.unsupported("struct variant"))
+    }
+}
+
+/// Serializes each element of a sequence as a repetition of the same key.
+pub(crate) struct RepeatSerializer<'a, W> {
+    ser: &'a mut DatDocumentSerializer<W>,
+    key: &'a str,
+    level: Level,
+}
+
+impl<'a, W: Write> SerializeSeq for RepeatSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(ValueSerializer::new(self.ser, self.key, self.level))
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> SerializeTuple for RepeatSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> SerializeTupleStruct for RepeatSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the fields of a structure as the items of a fragment or sub-entry.
+///
+/// A sub-entry whose fields are all `None` or empty is not written at all, since it could not
+/// be parsed.
+pub(crate) struct EntriesSerializer<'a, W> {
+    ser: &'a mut DatDocumentSerializer<W>,
+    parent: Level,
+    level: Level,
+    /// The mark of the opening of a sub-entry, or `None` for a fragment.
+    mark: Option<usize>,
+    key: Option<String>,
+}
+
+impl<'a, W: Write> EntriesSerializer<'a, W> {
+    fn close(self) -> Result<()> {
+        match (self.mark, self.parent) {
+            (None, _) => self.ser.write_str(")\n"),
+            (Some(mark), Level::SubEntry) => self.ser.close_sub_entry(mark, " )"),
+            (Some(mark), _) => self.ser.close_sub_entry(mark, " )\n"),
+        }
+    }
+}
+
+impl<'a, W: Write> SerializeStruct for EntriesSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(ValueSerializer::new(self.ser, key, self.level))
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<'a, W: Write> SerializeMap for EntriesSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.key.take() {
+            Some(key) => value.serialize(ValueSerializer::new(self.ser, &key, self.level)),
+            None => Err(Error::SerdeError("key is missing".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+/// Serializes map keys into strings.
+pub(crate) struct MapKeySerializer;
+
+fn key_must_be_a_string() -> Error {
+    Error::SerdeError("map key must be a string".to_string())
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
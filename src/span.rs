@@ -0,0 +1,180 @@
+//! Positions of parsed elements within their input.
+//!
+//! Keys and values parsed from a DAT borrow from the input, so their positions can be found
+//! from where they point into it, without tracking positions while parsing. Quoted values span
+//! their quotes, and fragments and sub-entries span their parentheses. Values that contain
+//! escape sequences are copied when they are parsed, and so have no position, and neither do
+//! keys and values that were added after parsing. A fragment or sub-entry whose first key or
+//! last value has no position spans from its first or to its last element that has one.
+//!
+//! [`LineIndex`](struct.LineIndex.html) converts byte offsets into lines and columns.
+//!
+//! ```rust
+//! use listinfo::parse;
+//! use listinfo::span::LineIndex;
+//!
+//! const DAT: &str = "game (\n\tname \"Cave Story (En)\"\n\trom ( name Doukutsu.exe size 1478656 )\n)";
+//!
+//! let document = parse::parse_document(DAT).unwrap();
+//! let game = document.entry("game").unwrap().next().unwrap();
+//! let rom = game.entry_unique("rom").unwrap();
+//! assert_eq!(rom.span(DAT), Some("( name Doukutsu.exe size 1478656 )"));
+//! let name = game.entry_unique("name").unwrap();
+//! assert_eq!(name.span(DAT), Some("\"Cave Story (En)\""));
+//!
+//! let lines = LineIndex::new(DAT);
+//! let range = rom.byte_range(DAT).unwrap();
+//! assert_eq!(lines.line_column(range.start), Some((3, 6)));
+//! assert_eq!(lines.line(3), Some("\trom ( name Doukutsu.exe size 1478656 )"));
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use indexmap::IndexMap;
+
+use crate::elements::*;
+
+/// Gets the byte range of a slice within the input, if it points into the input.
+pub fn byte_range(input: &str, slice: &str) -> Option<Range<usize>> {
+    let start = (slice.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    let end = start.checked_add(slice.len())?;
+    if end <= input.len() {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+/// Joins two byte ranges into the range that covers both.
+fn join(a: Option<Range<usize>>, b: Option<Range<usize>>) -> Option<Range<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Extends a byte range over the delimiters around it, if they are there.
+///
+/// Whitespace between the range and the delimiters is included.
+fn enclose(input: &str, range: Range<usize>, open: char, close: char) -> Range<usize> {
+    let before = input[..range.start].trim_end_matches(char::is_whitespace);
+    let after = input[range.end..].trim_start_matches(char::is_whitespace);
+    if before.ends_with(open) && after.starts_with(close) {
+        before.len() - open.len_utf8()..input.len() - after.len() + close.len_utf8()
+    } else {
+        range
+    }
+}
+
+/// Gets the byte range covered by a fragment or sub-entry, including its parentheses.
+fn entries_range(
+    input: &str,
+    keys: &IndexMap<Cow<'_, str>, Node<EntryData<'_>>>,
+) -> Option<Range<usize>> {
+    let range = keys.iter().fold(None, |range, (key, node)| {
+        let range = join(range, byte_range(input, key));
+        node.as_slice()
+            .iter()
+            .fold(range, |range, value| join(range, value.byte_range(input)))
+    })?;
+    Some(enclose(input, range, '(', ')'))
+}
+
+/// Implements the span methods shared by `EntryFragment` and `SubEntry`.
+macro_rules! impl_entries_span {
+    ($ty:ident) => {
+        impl<'a> $ty<'a> {
+            /// Gets the byte range in the input from the opening to the closing parenthesis.
+            pub fn byte_range(&self, input: &str) -> Option<Range<usize>> {
+                entries_range(input, &self.keys)
+            }
+
+            /// Gets the input from the opening to the closing parenthesis.
+            pub fn span<'i>(&self, input: &'i str) -> Option<&'i str> {
+                input.get(self.byte_range(input)?)
+            }
+
+            /// Gets the byte range in the input of the first occurrence of the given key.
+            pub fn key_range(&self, key: &str, input: &str) -> Option<Range<usize>> {
+                let (key, _) = self.keys.get_key_value(key)?;
+                byte_range(input, key)
+            }
+        }
+    };
+}
+
+impl_entries_span!(EntryFragment);
+impl_entries_span!(SubEntry);
+
+impl<'a> EntryData<'a> {
+    /// Gets the byte range in the input of the value or sub-entry.
+    ///
+    /// The range of a quoted value includes its quotes.
+    pub fn byte_range(&self, input: &str) -> Option<Range<usize>> {
+        match self {
+            EntryData::Scalar(Cow::Borrowed(value)) => {
+                byte_range(input, value).map(|range| enclose(input, range, '"', '"'))
+            }
+            EntryData::Scalar(Cow::Owned(_)) => None,
+            EntryData::SubEntry(sub_entry) => sub_entry.byte_range(input),
+        }
+    }
+
+    /// Gets the input covered by the value or sub-entry.
+    pub fn span<'i>(&self, input: &'i str) -> Option<&'i str> {
+        input.get(self.byte_range(input)?)
+    }
+}
+
+/// Converts byte offsets into the input to lines and columns.
+///
+/// Lines and columns are 1-based, and columns count characters,
+/// as in [`ParseError`](../struct.ParseError.html).
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    input: &'a str,
+    /// The byte offset of the start of every line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Indexes the lines of the input.
+    pub fn new(input: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(input.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { input, line_starts }
+    }
+
+    /// The number of lines in the input.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Gets the line and column of a byte offset into the input.
+    ///
+    /// Returns `None` if the offset is past the end of the input or within a character.
+    pub fn line_column(&self, offset: usize) -> Option<(usize, usize)> {
+        if !self.input.is_char_boundary(offset) {
+            return None;
+        }
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let column = self.input[self.line_starts[line]..offset].chars().count();
+        Some((line + 1, column + 1))
+    }
+
+    /// Gets the text of a line without its line ending.
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.input.len(), |next| next - 1);
+        Some(self.input[start..end].trim_end_matches('\r'))
+    }
+}
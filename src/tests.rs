@@ -1,8 +1,14 @@
 use crate::elements::*;
 use crate::parse;
+use crate::write::*;
+use crate::Error;
+use alloc::borrow::Cow;
 
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
 
 #[test]
 fn test_parse_header() {
@@ -17,19 +23,19 @@ fn test_parse_header() {
     let (_, header) = parse::parse_fragment(HEADER).unwrap();
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Test"))
+        Some(&EntryData::Scalar("Test".into()))
     );
     assert_eq!(
         header.entry_unique("description"),
-        Some(&EntryData::Scalar("Test Description"))
+        Some(&EntryData::Scalar("Test Description".into()))
     );
     assert_eq!(
         header.entry_unique("version"),
-        Some(&EntryData::Scalar("42069"))
+        Some(&EntryData::Scalar("42069".into()))
     );
     assert_eq!(
         header.entry_unique("author"),
-        Some(&EntryData::Scalar("TestAuthor"))
+        Some(&EntryData::Scalar("TestAuthor".into()))
     );
 }
 
@@ -47,19 +53,19 @@ fn test_parse_header_usability() {
     let (_, header) = parse::parse_fragment(&header_str).unwrap();
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Test"))
+        Some(&EntryData::Scalar("Test".into()))
     );
     assert_eq!(
         header.entry_unique("description"),
-        Some(&EntryData::Scalar("Test Description"))
+        Some(&EntryData::Scalar("Test Description".into()))
     );
     assert_eq!(
         header.entry_unique("version"),
-        Some(&EntryData::Scalar("42069"))
+        Some(&EntryData::Scalar("42069".into()))
     );
     assert_eq!(
         header.entry_unique("author"),
-        Some(&EntryData::Scalar("TestAuthor"))
+        Some(&EntryData::Scalar("TestAuthor".into()))
     );
 }
 
@@ -76,7 +82,7 @@ fn test_parse_singular_iter() {
 
     // Test singular iterator
     for val in header.entry_iter("name").unwrap() {
-        assert_eq!(val, &EntryData::Scalar("Test"));
+        assert_eq!(val, &EntryData::Scalar("Test".into()));
     }
 }
 
@@ -90,12 +96,17 @@ fn test_parse_multi_unique() {
 
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Test"))
+        Some(&EntryData::Scalar("Test".into()))
     );
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Test"))
+        Some(&EntryData::Scalar("Test".into()))
     );
+    assert_eq!(
+        header.entry("name").and_then(Node::unique),
+        Some(&EntryData::Scalar("Test".into()))
+    );
+    assert_eq!(Node::<EntryData<'_>>::Many(Vec::new()).unique(), None);
 }
 
 #[test]
@@ -110,10 +121,15 @@ fn test_parse_game() {
         )"#;
 
     let (_, game) = parse::parse_fragment(GAME).unwrap();
-    assert_eq!(game.entry_unique("name"), Some(&EntryData::Scalar("Test")));
+    assert_eq!(
+        game.entry_unique("name"),
+        Some(&EntryData::Scalar("Test".into()))
+    );
     assert_eq!(
         game.entry_unique("description"),
-        Some(&EntryData::Scalar("SCPH-101 (Version 4.4 03/24/00 A)"))
+        Some(&EntryData::Scalar(
+            "SCPH-101 (Version 4.4 03/24/00 A)".into()
+        ))
     );
 
     let iter = game.entry_iter("rom");
@@ -141,7 +157,7 @@ fn test_parse_game() {
     let iter = game.entry_iter("sample");
     if let Some(sample) = iter {
         for sample in sample {
-            if let &EntryData::Scalar(value) = sample {
+            if let EntryData::Scalar(value) = sample {
                 assert_eq!(value, "hello");
             } else {
                 unreachable!()
@@ -179,19 +195,19 @@ fn test_parse_document() {
         for fragment in header {
             assert_eq!(
                 fragment.entry_unique("name"),
-                Some(&EntryData::Scalar("Test"))
+                Some(&EntryData::Scalar("Test".into()))
             );
             assert_eq!(
                 fragment.entry_unique("description"),
-                Some(&EntryData::Scalar("Test Description"))
+                Some(&EntryData::Scalar("Test Description".into()))
             );
             assert_eq!(
                 fragment.entry_unique("version"),
-                Some(&EntryData::Scalar("42069"))
+                Some(&EntryData::Scalar("42069".into()))
             );
             assert_eq!(
                 fragment.entry_unique("author"),
-                Some(&EntryData::Scalar("TestAuthor"))
+                Some(&EntryData::Scalar("TestAuthor".into()))
             );
         }
     }
@@ -200,11 +216,13 @@ fn test_parse_document() {
         for game in games {
             assert_eq!(
                 game.entry_unique("name"),
-                Some(&EntryData::Scalar("psone-44a"))
+                Some(&EntryData::Scalar("psone-44a".into()))
             );
             assert_eq!(
                 game.entry_unique("description"),
-                Some(&EntryData::Scalar("SCPH-101 (Version 4.4 03/24/00 A)"))
+                Some(&EntryData::Scalar(
+                    "SCPH-101 (Version 4.4 03/24/00 A)".into()
+                ))
             );
 
             let iter = game.entry_iter("rom");
@@ -231,7 +249,7 @@ fn test_parse_document() {
             let iter = game.entry_iter("sample");
             if let Some(sample) = iter {
                 for sample in sample {
-                    if let &EntryData::Scalar(value) = sample {
+                    if let EntryData::Scalar(value) = sample {
                         assert_eq!(value, "hello");
                     } else {
                         unreachable!()
@@ -271,15 +289,15 @@ fn parse_cave_story() {
     let rom = game.entry_unique("rom").unwrap();
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Cave Story"))
+        Some(&EntryData::Scalar("Cave Story".into()))
     );
     assert_eq!(
         game.entry_unique("name"),
-        Some(&EntryData::Scalar("Cave Story (En)"))
+        Some(&EntryData::Scalar("Cave Story (En)".into()))
     );
     assert_eq!(
         header.entry_unique("name"),
-        Some(&EntryData::Scalar("Cave Story"))
+        Some(&EntryData::Scalar("Cave Story".into()))
     );
     if let EntryData::SubEntry(rom) = rom {
         assert_eq!(rom.value_unique("name"), Some("Doukutsu.exe"))
@@ -301,3 +319,1742 @@ fn parse_inner_braces() {
         panic!()
     }
 }
+
+#[test]
+fn write_document_round_trip() {
+    const DOCUMENT: &str = r#"clrmamepro (
+	name Test
+	description "Test Description"
+)
+
+game (
+	name psone-44a
+	description "SCPH-101 (Version 4.4 03/24/00 A)"
+	rom ( name psone-44a.bin size 524288 crc 6a0e22a0 )
+	rom ( name psone-44b.bin size 524288 crc 6a0e22a0 )
+	sample hello
+	sample "hello world"
+)
+"#;
+
+    let doc = parse::parse_document(DOCUMENT).unwrap();
+    let mut output = String::new();
+    doc.write_to(&mut output, &WriteOptions::default()).unwrap();
+    assert_eq!(output, DOCUMENT);
+    assert_eq!(doc.to_string(), output);
+}
+
+#[test]
+fn write_document_options() {
+    const GAME: &str = r#"game (
+            name "Test Game"
+            rom ( name test.bin size 1 )
+        )"#;
+
+    let doc = parse::parse_document(GAME).unwrap();
+    let options = WriteOptions {
+        indent: Indent::Spaces(4),
+        line_ending: LineEnding::CrLf,
+        inline_sub_entries: false,
+    };
+    let mut output = String::new();
+    doc.write_to(&mut output, &options).unwrap();
+    assert_eq!(
+        output,
+        "game (\r\n    name \"Test Game\"\r\n    rom (\r\n        name test.bin\r\n        size 1\r\n    )\r\n)\r\n"
+    );
+
+    let (key, game) = parse::parse_fragment(GAME).unwrap();
+    let mut fragment = String::new();
+    game.write_to(key, &mut fragment, &options).unwrap();
+    assert_eq!(fragment, output);
+}
+
+#[test]
+fn parse_error_location() {
+    const DOCUMENT: &str = r#"clrmamepro (
+	name "Test"
+)
+
+game (
+	name "Foo (USA)"
+	description "Foo (USA)"
+	rom ( name foo.bin size 1 crc 00000000 )
+	year
+)
+"#;
+
+    let err = match parse::parse_document(DOCUMENT) {
+        Err(crate::Error::ParseError(err)) => err,
+        _ => panic!("expected a parse error"),
+    };
+    assert_eq!(err.fragment(), Some("game"));
+    assert_eq!(err.name(), Some("Foo (USA)"));
+    assert_eq!(err.line(), 9);
+    assert_eq!(err.column(), 6);
+    assert_eq!(err.snippet(), "\tyear");
+    assert_eq!(
+        err.to_string(),
+        "expected whitespace at line 9, column 6 in game (name \"Foo (USA)\")\n  |\n9 | \tyear\n  | \t    ^"
+    );
+}
+
+#[test]
+fn parse_escaped_strings() {
+    const GAME: &str = r#"game (
+            name "Foo \"Bar\" (USA)"
+            description "Foo (USA)"
+            comment "C:\DATs\\"
+            manufacturer ""
+            rom ( name "\"quoted\".bin" size 1 )
+        )"#;
+
+    let (_, game) = parse::parse_fragment(GAME).unwrap();
+    assert_eq!(
+        game.entry_unique("name"),
+        Some(&EntryData::Scalar(Cow::Owned(String::from(
+            "Foo \"Bar\" (USA)"
+        ))))
+    );
+    assert!(matches!(
+        game.entry_unique("description"),
+        Some(EntryData::Scalar(Cow::Borrowed("Foo (USA)")))
+    ));
+    assert_eq!(
+        game.entry_unique("comment"),
+        Some(&EntryData::Scalar("C:\\DATs\\".into()))
+    );
+    assert_eq!(
+        game.entry_unique("manufacturer"),
+        Some(&EntryData::Scalar("".into()))
+    );
+    if let Some(EntryData::SubEntry(rom)) = game.entry_unique("rom") {
+        assert_eq!(rom.value_unique("name"), Some("\"quoted\".bin"));
+    } else {
+        panic!()
+    }
+
+    let mut output = String::new();
+    game.write_to("game", &mut output, &WriteOptions::default())
+        .unwrap();
+    assert_eq!(
+        output,
+        "game (\n\tname \"Foo \\\"Bar\\\" (USA)\"\n\tdescription \"Foo (USA)\"\n\tcomment C:\\DATs\\\n\tmanufacturer \"\"\n\trom ( name \"\\\"quoted\\\".bin\" size 1 )\n)\n"
+    );
+    let (_, reparsed) = parse::parse_fragment(&output).unwrap();
+    assert_eq!(reparsed.entry_unique("name"), game.entry_unique("name"));
+    assert_eq!(
+        reparsed.entry_unique("comment"),
+        game.entry_unique("comment")
+    );
+}
+
+const WINDOWS_DAT: &str = concat!(
+    "\u{feff}clrmamepro (\r\n",
+    "\tname \"Nintendo - Game Boy\"\r\n",
+    "\tdescription\t\"Nintendo - Game Boy\"\r\n",
+    "\tversion   20200101-000000\r\n",
+    ")\r\n",
+    "\r\n",
+    "game(\r\n",
+    "\tname \"Tetris (World) (Rev 1)\"\r\n",
+    "\tregion\tWorld\r\n",
+    "\trom(name \"Tetris (World) (Rev 1).gb\"\tsize 32768  crc 46df91ad md5 084f1e457749cdec86183189bd88ce69 sha1 74591cc9501af93873f9a5d3eb12da12c0723bbc )\r\n",
+    ")\r\n",
+    "\r\n",
+    "game (\r\n",
+    "\tname \"Tetris (Japan)\"\r\n",
+    "\trom ( name \"Tetris (Japan).gb\" size 32768 crc 63f9407d )\r\n",
+    ")\r\n",
+);
+
+#[test]
+fn parse_windows_dat() {
+    let doc = parse::parse_document(WINDOWS_DAT).unwrap();
+    let header = doc.entry("clrmamepro").unwrap().next().unwrap();
+    assert_eq!(
+        header.entry_unique("description"),
+        Some(&EntryData::Scalar("Nintendo - Game Boy".into()))
+    );
+    assert_eq!(
+        header.entry_unique("version"),
+        Some(&EntryData::Scalar("20200101-000000".into()))
+    );
+
+    let mut games = doc.entry("game").unwrap();
+    let game = games.next().unwrap();
+    assert_eq!(
+        game.entry_unique("region"),
+        Some(&EntryData::Scalar("World".into()))
+    );
+    if let Some(EntryData::SubEntry(rom)) = game.entry_unique("rom") {
+        assert_eq!(rom.value_unique("name"), Some("Tetris (World) (Rev 1).gb"));
+        assert_eq!(rom.value_unique("size"), Some("32768"));
+        assert_eq!(rom.value_unique("crc"), Some("46df91ad"));
+        assert_eq!(
+            rom.value_unique("sha1"),
+            Some("74591cc9501af93873f9a5d3eb12da12c0723bbc")
+        );
+    } else {
+        panic!()
+    }
+
+    let game = games.next().unwrap();
+    if let Some(EntryData::SubEntry(rom)) = game.entry_unique("rom") {
+        assert_eq!(rom.value_unique("crc"), Some("63f9407d"));
+    } else {
+        panic!()
+    }
+}
+
+#[test]
+fn parse_single_line_dat() {
+    const DAT: &str = r#"clrmamepro ( name "Test" ) game ( name "Foo (USA)" rom ( name foo.bin size 1 crc 00000000 ) rom ( name bar.bin size 2 ) sample foo )"#;
+
+    let doc = parse::parse_document(DAT).unwrap();
+    let game = doc.entry("game").unwrap().next().unwrap();
+    assert_eq!(
+        game.entry_unique("name"),
+        Some(&EntryData::Scalar("Foo (USA)".into()))
+    );
+    assert_eq!(game.entry_iter("rom").unwrap().count(), 2);
+    assert_eq!(
+        game.entry_unique("sample"),
+        Some(&EntryData::Scalar("foo".into()))
+    );
+}
+
+#[test]
+fn parse_windows_dat_round_trip() {
+    let doc = parse::parse_document(WINDOWS_DAT).unwrap();
+    let options = WriteOptions {
+        line_ending: LineEnding::CrLf,
+        ..WriteOptions::default()
+    };
+    let mut output = String::new();
+    doc.write_to(&mut output, &options).unwrap();
+    assert_eq!(
+        output,
+        WINDOWS_DAT
+            .trim_start_matches('\u{feff}')
+            .replace("game(", "game (")
+            .replace("rom(name", "rom ( name")
+            .replace("\tdescription\t", "\tdescription ")
+            .replace("\tversion   ", "\tversion ")
+            .replace("\tregion\t", "\tregion ")
+            .replace("\"\tsize 32768  crc", "\" size 32768 crc")
+    );
+}
+
+#[test]
+fn parse_nested_sub_entries() {
+    const GAME: &str = r#"game (
+            name "Nested"
+            rom ( name a.bin size 1 )
+            driver ( status good emulation ( state good color ( depth 8 ) ) )
+            rom ( name b.bin size 2 )
+        )"#;
+
+    let (_, game) = parse::parse_fragment(GAME).unwrap();
+    let keys: Vec<&str> = game.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["name", "rom", "driver"]);
+    assert_eq!(
+        game.entry_iter("rom")
+            .unwrap()
+            .filter_map(EntryData::as_sub_entry)
+            .filter_map(|rom| rom.value_unique("name"))
+            .collect::<Vec<_>>(),
+        ["a.bin", "b.bin"]
+    );
+
+    let driver = game
+        .entry_unique("driver")
+        .and_then(EntryData::as_sub_entry);
+    let driver = driver.expect("driver should be a sub-entry");
+    assert_eq!(driver.value_unique("status"), Some("good"));
+    assert_eq!(driver.value_unique("emulation"), None);
+    let emulation = driver
+        .entry_unique("emulation")
+        .and_then(EntryData::as_sub_entry)
+        .expect("emulation should be a sub-entry");
+    assert_eq!(emulation.value_unique("state"), Some("good"));
+    let color = emulation
+        .entry_unique("color")
+        .and_then(EntryData::as_sub_entry)
+        .expect("color should be a sub-entry");
+    assert_eq!(color.value_unique("depth"), Some("8"));
+
+    let mut output = String::new();
+    game.write_to("game", &mut output, &WriteOptions::default())
+        .unwrap();
+    assert_eq!(
+        output,
+        "game (\n\tname Nested\n\trom ( name a.bin size 1 )\n\trom ( name b.bin size 2 )\n\tdriver ( status good emulation ( state good color ( depth 8 ) ) )\n)\n"
+    );
+
+    let options = WriteOptions {
+        inline_sub_entries: false,
+        ..WriteOptions::default()
+    };
+    let mut output = String::new();
+    driver.write_to("driver", &mut output, &options).unwrap();
+    assert_eq!(
+        output,
+        "driver (\n\tstatus good\n\temulation (\n\t\tstate good\n\t\tcolor (\n\t\t\tdepth 8\n\t\t)\n\t)\n)\n"
+    );
+    let (_, reparsed) = parse::parse_fragment(&output).unwrap();
+    assert_eq!(
+        reparsed.entry_unique("emulation"),
+        driver.entry_unique("emulation")
+    );
+}
+
+#[test]
+fn parse_fragments_lazily() {
+    const DAT: &str = "clrmamepro ( name \"Cave Story\" )\ngame ( name a )\ngame ( name b )\n";
+
+    let keys: Vec<&str> = parse::fragments(DAT)
+        .map(|fragment| fragment.unwrap().0)
+        .collect();
+    assert_eq!(keys, ["clrmamepro", "game", "game"]);
+
+    let mut fragments = parse::fragments("game ( name a )\ngame ( name b\ngame ( name c )");
+    assert!(fragments.next().unwrap().is_ok());
+    match fragments.next() {
+        Some(Err(Error::ParseError(err))) => assert_eq!(err.line(), 3),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    assert!(fragments.next().is_none());
+
+    assert!(parse::fragments("").next().unwrap().is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader_across_buffer_edges() {
+    use std::io::{BufReader, Cursor};
+
+    const DAT: &str = "\u{feff}clrmamepro ( name \"Cave (Story)\" )\n\ngame (\n\tname \"Cave Story \\\"(En)\\\"\"\n\tdescription \"Ça va\"\n\trom ( name ps-22j(v).bin size 1 )\n\tdriver ( status good emulation ( state good ) )\n)\n";
+
+    let document = parse::parse_document(DAT).unwrap();
+    let mut expected = String::new();
+    for (key, fragments) in document.iter() {
+        for fragment in fragments {
+            fragment
+                .write_to(key, &mut expected, &WriteOptions::default())
+                .unwrap();
+        }
+    }
+
+    // A single byte buffer splits every token and multi-byte character across reads.
+    let reader = parse::Reader::new(BufReader::with_capacity(1, Cursor::new(DAT)));
+    let mut output = String::new();
+    let mut keys = Vec::new();
+    for fragment in reader {
+        let (key, fragment) = fragment.unwrap();
+        fragment
+            .write_to(&key, &mut output, &WriteOptions::default())
+            .unwrap();
+        keys.push(key);
+    }
+    assert_eq!(keys, ["clrmamepro", "game"]);
+    assert_eq!(output, expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader_error_location() {
+    use std::io::Cursor;
+
+    const DAT: &str = "game ( name a )\ngame ( name b ) game (\n\tname c\n\tyear\n)\n";
+
+    let expected = match parse::parse_document(DAT) {
+        Err(Error::ParseError(err)) => err,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    let mut reader = parse::Reader::new(Cursor::new(DAT));
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_ok());
+    match reader.next() {
+        Some(Err(Error::ParseError(err))) => assert_eq!(err, expected),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    assert!(reader.next().is_none());
+
+    let mut reader = parse::Reader::new(Cursor::new("game ( name a )\ngame ( name b"));
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(reader.next(), Some(Err(Error::ParseError(_)))));
+    assert!(parse::Reader::new(Cursor::new("  \n"))
+        .next()
+        .unwrap()
+        .is_err());
+}
+
+#[test]
+fn parse_owned_document() {
+    let input = String::from(
+        "clrmamepro ( name \"Cave \\\"Story\\\"\" )\ngame ( name a rom ( name a.bin ) )\n",
+    );
+    let document = parse::parse_document(&input).unwrap();
+    let owned: OwnedDatDocument = document.clone().into_owned();
+    assert_eq!(owned, document);
+    drop(document);
+    drop(input);
+
+    #[cfg(feature = "std")]
+    let owned = std::thread::spawn(move || owned).join().unwrap();
+    let header = owned.entry("clrmamepro").unwrap().next().unwrap();
+    assert_eq!(
+        header.entry_unique("name"),
+        Some(&EntryData::Scalar("Cave \"Story\"".into()))
+    );
+    let game = owned.entry("game").unwrap().next().unwrap();
+    assert_eq!(
+        game.entry_unique("rom")
+            .and_then(EntryData::as_sub_entry)
+            .and_then(|rom| rom.value_unique("name")),
+        Some("a.bin")
+    );
+}
+
+#[test]
+fn edit_fragment() {
+    const GAME: &str = r#"game (
+            name "Cave Story (En)"
+            rom ( name Doukutsu.exe size 1478656 )
+            rom ( name data.pak size 42 )
+            sample a
+            sample b
+        )"#;
+
+    let (_, mut game) = parse::parse_fragment(GAME).unwrap();
+
+    game.retain(|key, data| {
+        key != "rom"
+            || data.as_sub_entry().and_then(|rom| rom.value_unique("name")) != Some("data.pak")
+    });
+    assert!(matches!(game.entry("rom"), Some(Node::Unique(_))));
+
+    game.retain(|key, data| key != "sample" || data.as_scalar() != Some("a"));
+    assert_eq!(
+        game.entry("sample"),
+        Some(&Node::Unique(EntryData::Scalar("b".into())))
+    );
+    game.push("sample", "c");
+
+    if let Some(EntryData::SubEntry(rom)) = game.entry_mut("rom").and_then(|mut n| n.next()) {
+        rom.insert("size", String::from("1478657"));
+        rom.push("crc", "c5a2a3f6");
+    }
+
+    assert!(game.rename("name", "description"));
+    assert!(!game.rename("name", "description"));
+    game.insert("name", "Cave Story");
+    assert!(game.rename("sample", "name"));
+    assert_eq!(
+        game.entry("name"),
+        Some(&Node::Many(vec![
+            EntryData::Scalar("b".into()),
+            EntryData::Scalar("c".into()),
+            EntryData::Scalar("Cave Story".into()),
+        ]))
+    );
+    assert!(game.remove("name").is_some());
+
+    let mut output = String::new();
+    game.write_to("game", &mut output, &WriteOptions::default())
+        .unwrap();
+    assert_eq!(
+        output,
+        "game (\n\tdescription \"Cave Story (En)\"\n\trom ( name Doukutsu.exe size 1478657 crc c5a2a3f6 )\n)\n"
+    );
+}
+
+#[test]
+fn edit_document() {
+    let mut document = DatDocument::new()
+        .with(
+            "clrmamepro",
+            EntryFragment::new().with("name", "Cave Story"),
+        )
+        .with("game", EntryFragment::new().with("name", "a"))
+        .with("game", EntryFragment::new().with("name", "b"));
+
+    document.retain(|key, fragment| {
+        key != "game" || fragment.entry_unique("name").and_then(EntryData::as_scalar) != Some("a")
+    });
+    for game in document.entry_mut("game").unwrap() {
+        game.push(
+            "rom",
+            SubEntry::new()
+                .with("name", "b.bin")
+                .with("status", SubEntry::new().with("dump", "good")),
+        );
+    }
+    document.push("game", EntryFragment::new().with("name", "c"));
+    assert_eq!(
+        document
+            .remove("clrmamepro")
+            .map(|fragments| fragments.len()),
+        Some(1)
+    );
+
+    assert_eq!(
+        document.to_string(),
+        "game (\n\tname b\n\trom ( name b.bin status ( dump good ) )\n)\n\ngame (\n\tname c\n)\n"
+    );
+    let reparsed = parse::parse_document(&document.to_string())
+        .unwrap()
+        .into_owned();
+    assert_eq!(reparsed, document);
+}
+
+#[test]
+fn parse_hashes() {
+    use crate::hash::*;
+    use crate::ParseHashError;
+
+    let crc: Crc32 = "C5A2A3F6".parse().unwrap();
+    assert_eq!(crc, Crc32([0xc5, 0xa2, 0xa3, 0xf6]));
+    assert_eq!(crc, "c5a2a3f6".parse().unwrap());
+    assert_eq!(u32::from(crc), 0xc5a2a3f6);
+    assert_eq!(crc.to_string(), "c5a2a3f6");
+    assert_eq!(format!("{:X}", crc), "C5A2A3F6");
+    assert_eq!(format!("{:?}", crc), "Crc32(c5a2a3f6)");
+
+    let sha1: Sha1 = "bb2d0441e073da9c584f23c2ad8c7ab8aac293bf".parse().unwrap();
+    assert_eq!(sha1.to_string(), "bb2d0441e073da9c584f23c2ad8c7ab8aac293bf");
+    assert!(Md5([0; 16]) < Md5([0xff; 16]));
+
+    assert_eq!(
+        "c5a2a3".parse::<Crc32>(),
+        Err(ParseHashError::InvalidLength {
+            expected: 8,
+            found: 6
+        })
+    );
+    assert_eq!(
+        "c5a2a3fg".parse::<Crc32>(),
+        Err(ParseHashError::InvalidCharacter {
+            character: 'g',
+            index: 7
+        })
+    );
+    assert_eq!(
+        "c5a2é3f".parse::<Crc32>(),
+        Err(ParseHashError::InvalidCharacter {
+            character: 'é',
+            index: 4
+        })
+    );
+}
+
+#[test]
+fn graph_sets() {
+    use crate::graph::*;
+    use alloc::vec;
+
+    const DAT: &str = r#"resource ( name neogeo )
+    game ( name mslug romof neogeo )
+    game ( name mslugj cloneof mslug romof mslug )
+    game ( name mslugu cloneof mslug romof mslug )
+    game ( name pacman )
+    game ( name puckman cloneof pacman romof pacman )
+    game ( name orphan cloneof missing romof missing )
+    game ( name pacman )
+    game ( name loop1 cloneof loop2 )
+    game ( name loop2 romof loop1 )"#;
+
+    let document = parse::parse_document(DAT).unwrap();
+    let graph = SetGraph::new(&document);
+
+    assert_eq!(graph.sets().next(), Some("neogeo"));
+    assert_eq!(graph.sets().count(), 9);
+    assert_eq!(graph.parent_of("mslugj"), Some("mslug"));
+    assert_eq!(graph.parent_of("mslug"), None);
+    assert_eq!(graph.romof("mslug"), Some("neogeo"));
+    assert_eq!(graph.clones_of("mslug"), ["mslugj", "mslugu"]);
+    assert!(graph.clones_of("neogeo").is_empty());
+    assert!(graph.clones_of("nothing").is_empty());
+
+    assert_eq!(graph.bios_of("mslugj"), Some("neogeo"));
+    assert_eq!(graph.bios_of("mslug"), Some("neogeo"));
+    assert_eq!(graph.bios_of("neogeo"), None);
+    assert_eq!(graph.bios_of("puckman"), None);
+    assert_eq!(graph.bios_of("orphan"), None);
+    assert_eq!(graph.bios_of("loop1"), None);
+
+    assert_eq!(
+        graph
+            .get("puckman")
+            .and_then(|set| set.entry_unique("cloneof"))
+            .and_then(EntryData::as_scalar),
+        Some("pacman")
+    );
+
+    assert_eq!(
+        graph.issues(),
+        [
+            GraphIssue::DuplicateSet("pacman"),
+            GraphIssue::DanglingReference {
+                set: "orphan",
+                key: "cloneof",
+                target: "missing"
+            },
+            GraphIssue::DanglingReference {
+                set: "orphan",
+                key: "romof",
+                target: "missing"
+            },
+            GraphIssue::Cycle(vec!["loop1", "loop2"]),
+        ]
+    );
+    assert_eq!(
+        graph.issues()[3].to_string(),
+        "sets inherit from each other in a loop: \"loop1\" -> \"loop2\" -> \"loop1\""
+    );
+}
+
+#[test]
+fn graph_rebuild_layouts() {
+    use crate::graph::Layout;
+
+    const DAT: &str = r#"clrmamepro ( name MAME forcemerging split )
+    resource (
+        name neogeo
+        rom ( name sp-s2.sp1 size 131072 crc 9036d879 )
+    )
+    machine (
+        name ym2610
+        rom ( name ym2610.bin size 16 crc 11111111 )
+    )
+    game (
+        name mslug
+        romof neogeo
+        device_ref ym2610
+        rom ( name sp-s2.sp1 merge sp-s2.sp1 size 131072 crc 9036d879 )
+        rom ( name 201-p1.bin size 2097152 crc 08d8daa5 )
+        rom ( name ym2610.bin size 16 crc 11111111 )
+        disk ( name mslug sha1 0000000000000000000000000000000000000000 )
+    )
+    game (
+        name mslugj
+        cloneof mslug
+        romof mslug
+        device_ref ( name ym2610 )
+        rom ( name sp-s2.sp1 merge sp-s2.sp1 size 131072 crc 9036d879 )
+        rom ( name 201-p1.bin merge 201-p1.bin size 2097152 crc 08d8daa5 )
+        rom ( name 201-j1.bin size 1024 crc 22222222 )
+        disk ( name mslug merge mslug sha1 0000000000000000000000000000000000000000 )
+    )"#;
+
+    fn files<'a>(document: &'a DatDocument<'a>, key: &str, name: &str) -> Vec<&'a str> {
+        document
+            .entry(key)
+            .unwrap()
+            .find(|set| set.entry_unique("name").and_then(EntryData::as_scalar) == Some(name))
+            .map(|set| {
+                ["rom", "disk"]
+                    .iter()
+                    .filter_map(|key| set.entry_iter(key))
+                    .flatten()
+                    .filter_map(|file| file.as_sub_entry()?.value_unique("name"))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn forcemerging<'a>(document: &'a DatDocument<'a>) -> Option<&'a str> {
+        document
+            .entry("clrmamepro")?
+            .next()?
+            .entry_unique("forcemerging")?
+            .as_scalar()
+    }
+
+    let document = parse::parse_document(DAT).unwrap();
+
+    let split = document.rebuild(Layout::Split);
+    assert_eq!(forcemerging(&split), Some("split"));
+    assert_eq!(files(&split, "resource", "neogeo"), ["sp-s2.sp1"]);
+    assert_eq!(files(&split, "machine", "ym2610"), ["ym2610.bin"]);
+    assert_eq!(files(&split, "game", "mslug"), ["201-p1.bin", "mslug"]);
+    assert_eq!(files(&split, "game", "mslugj"), ["201-j1.bin"]);
+
+    let merged = document.rebuild(Layout::Merged);
+    assert_eq!(forcemerging(&merged), Some("full"));
+    assert_eq!(merged.entry("game").unwrap().count(), 1);
+    assert_eq!(
+        files(&merged, "game", "mslug"),
+        ["201-p1.bin", "201-j1.bin", "mslug"]
+    );
+    assert_eq!(files(&merged, "resource", "neogeo"), ["sp-s2.sp1"]);
+
+    let non_merged = document.rebuild(Layout::NonMerged);
+    assert_eq!(forcemerging(&non_merged), Some("none"));
+    assert_eq!(
+        files(&non_merged, "game", "mslug"),
+        ["sp-s2.sp1", "201-p1.bin", "ym2610.bin", "mslug"]
+    );
+    assert_eq!(
+        files(&non_merged, "game", "mslugj"),
+        [
+            "sp-s2.sp1",
+            "201-p1.bin",
+            "201-j1.bin",
+            "ym2610.bin",
+            "mslug"
+        ]
+    );
+
+    let dat = split.to_string();
+    let reparsed = parse::parse_document(&dat).unwrap();
+    assert_eq!(reparsed.rebuild(Layout::Split), split);
+}
+
+#[cfg(feature = "logiqx")]
+const LOGIQX: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">
+<datafile>
+    <header>
+        <name>Nintendo - Game Boy</name>
+        <description>Nintendo - Game Boy</description>
+        <version>20230101-000000</version>
+        <homepage>No-Intro</homepage>
+        <clrmamepro forcenodump="required" header="No-Intro_GB.xml"/>
+    </header>
+    <!-- Parents come before their clones -->
+    <game name="Tetris (World) (Rev 1)">
+        <description>Tetris (World) (Rev 1)</description>
+        <rom name="Tetris (World) (Rev 1).gb" size="32768" crc="46DF91AD" sha1="74591CC9501AF93873F9A5D3EB12DA12C0723BBC"/>
+    </game>
+    <game name="Tetris (Japan)" cloneof="Tetris (World) (Rev 1)">
+        <description><![CDATA[Tetris (Japan)]]></description>
+        <comment>Tom &amp; Jerry</comment>
+        <rom name="Tetris (Japan).gb" size="32768" crc="6ab7b1fb"/>
+        <rom name="Tetris (Japan) &amp; Extra.gb" size="0"/>
+    </game>
+</datafile>"#;
+
+#[cfg(feature = "logiqx")]
+#[test]
+fn parse_logiqx_document() {
+    use crate::logiqx;
+
+    let document = logiqx::parse_document(LOGIQX).unwrap();
+    let header = document.entry("clrmamepro").unwrap().next().unwrap();
+    assert_eq!(
+        header.entry_unique("name").and_then(EntryData::as_scalar),
+        Some("Nintendo - Game Boy")
+    );
+    assert_eq!(
+        header.entry_unique("header").and_then(EntryData::as_scalar),
+        Some("No-Intro_GB.xml")
+    );
+    assert_eq!(
+        header
+            .entry_unique("forcenodump")
+            .and_then(EntryData::as_scalar),
+        Some("required")
+    );
+
+    let games: Vec<_> = document.entry("game").unwrap().collect();
+    assert_eq!(games.len(), 2);
+    assert!(matches!(
+        games[0].entry_unique("name"),
+        Some(EntryData::Scalar(Cow::Borrowed("Tetris (World) (Rev 1)")))
+    ));
+    assert!(matches!(
+        games[1].entry_unique("comment"),
+        Some(EntryData::Scalar(Cow::Owned(comment))) if comment == "Tom & Jerry"
+    ));
+    assert_eq!(
+        games[1]
+            .entry_unique("description")
+            .and_then(EntryData::as_scalar),
+        Some("Tetris (Japan)")
+    );
+    assert_eq!(
+        games[1]
+            .entry_unique("cloneof")
+            .and_then(EntryData::as_scalar),
+        Some("Tetris (World) (Rev 1)")
+    );
+
+    let roms: Vec<_> = games[1]
+        .entry_iter("rom")
+        .unwrap()
+        .filter_map(EntryData::as_sub_entry)
+        .collect();
+    assert_eq!(roms[0].value_unique("crc"), Some("6ab7b1fb"));
+    assert_eq!(
+        roms[1].value_unique("name"),
+        Some("Tetris (Japan) & Extra.gb")
+    );
+}
+
+#[cfg(feature = "logiqx")]
+#[test]
+fn parse_logiqx_errors() {
+    use crate::logiqx;
+
+    let err = logiqx::parse_document("<datafile><game name=\"a\"></datafile>").unwrap_err();
+    assert!(matches!(err, Error::XmlError(_)));
+
+    let err = logiqx::parse_document("<mame><machine name=\"a\"/></mame>").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a <datafile> element, found <mame>"
+    );
+
+    let err = logiqx::parse_document("<datafile><game name=\"a\">").unwrap_err();
+    assert!(err.to_string().starts_with("unexpected end of input"));
+
+    assert!(logiqx::parse_document("").is_err());
+    assert!(logiqx::parse_document("<datafile/>")
+        .unwrap()
+        .iter()
+        .next()
+        .is_none());
+}
+
+#[cfg(feature = "logiqx")]
+#[test]
+fn write_logiqx_round_trip() {
+    use crate::logiqx;
+
+    const DAT: &str = r#"clrmamepro (
+        name "Tom & Jerry"
+        description "Tom & Jerry"
+        forcemerging split
+        version 1
+        author "<someone>"
+    )
+
+    game (
+        name "Tetris (Japan)"
+        description "Tetris \"Japan\""
+        cloneof "Tetris (World)"
+        rom ( name "Tetris (Japan).gb" size 32768 crc 6ab7b1fb )
+        rom ( name "Tetris & Extra.gb" size 0 )
+        driver ( status good emulation ( state good ) )
+        year ""
+    )"#;
+
+    let document = parse::parse_document(DAT).unwrap();
+    let xml = logiqx::to_string(&document).unwrap();
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">
+<datafile>
+	<header>
+		<name>Tom &amp; Jerry</name>
+		<description>Tom &amp; Jerry</description>
+		<version>1</version>
+		<author>&lt;someone&gt;</author>
+		<clrmamepro forcemerging="split"/>
+	</header>
+	<game name="Tetris (Japan)" cloneof="Tetris (World)">
+		<description>Tetris "Japan"</description>
+		<year/>
+		<rom name="Tetris (Japan).gb" size="32768" crc="6ab7b1fb"/>
+		<rom name="Tetris &amp; Extra.gb" size="0"/>
+	</game>
+</datafile>
+"#
+    );
+
+    // The Logiqx DTD has no `driver` element.
+    let mut document = document;
+    for game in document.entry_mut("game").unwrap() {
+        game.remove("driver");
+    }
+    let reparsed = logiqx::parse_document(&xml).unwrap();
+    assert_eq!(reparsed, document);
+    assert_eq!(logiqx::to_string(&reparsed).unwrap(), xml);
+
+    let document = parse::parse_document(
+        "clrmamepro ( name a ) clrmamepro ( name b ) \
+         game ( name a sample b sample c archive d rom ( name r size 1 size 2 extra ( x y ) ) )",
+    )
+    .unwrap();
+    let xml = logiqx::to_string(&document).unwrap();
+    assert_eq!(xml.matches("<header>").count(), 1);
+    assert!(xml.contains(
+        "\t<game name=\"a\">\n\t\t<description/>\n\t\t<rom name=\"r\" size=\"1\"/>\n\t\t<sample name=\"b\"/>\n\t\t<sample name=\"c\"/>\n\t\t<archive name=\"d\"/>\n\t</game>\n"
+    ));
+
+    // Resources are written as BIOS sets, and sets and elements the DTD has no place for are
+    // left out, while the values it requires are written empty.
+    let document = parse::parse_document(
+        "clrmamepro ( name h ) resource ( name neogeo description \"Neo Geo\" ) \
+         machine ( name m ) game ( description a description b year 1 year 2 rom ( name r ) \
+         disk d rom x release ( region EUR extra 1 ) )",
+    )
+    .unwrap();
+    let xml = logiqx::to_string(&document).unwrap();
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">
+<datafile>
+	<header>
+		<name>h</name>
+		<description/>
+		<version/>
+		<author/>
+	</header>
+	<game name="neogeo" isbios="yes">
+		<description>Neo Geo</description>
+	</game>
+	<game name="">
+		<description>a</description>
+		<year>1</year>
+		<release name="" region="EUR"/>
+		<rom name="r" size=""/>
+	</game>
+</datafile>
+"#
+    );
+
+    let (_, game) = parse::parse_fragment(
+        "game ( name a description a sample b sample c archive d rom ( name r size 1 ) )",
+    )
+    .unwrap();
+    let document = DatDocument::new().with("game", game);
+    assert_eq!(
+        logiqx::parse_document(&logiqx::to_string(&document).unwrap()).unwrap(),
+        document
+    );
+}
+
+#[cfg(feature = "listxml")]
+#[test]
+fn parse_listxml_document() {
+    use crate::graph::{Layout, SetGraph};
+    use crate::listxml;
+
+    const LISTXML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE mame [
+<!ELEMENT mame (machine+)>
+    <!ATTLIST mame build CDATA #IMPLIED>
+<!ELEMENT machine (description, year?, manufacturer?, rom*, device_ref*, driver?)>
+]>
+
+<mame build="0.250 (mame0250)" debug="no" mameconfig="10">
+    <machine name="neogeo" sourcefile="neogeo/neogeo.cpp" isbios="yes">
+        <description>Neo-Geo MV-6F</description>
+        <biosset name="euro" description="Europe MVS (Ver. 2)" default="yes"/>
+        <rom name="sp-s2.sp1" bios="euro" size="131072" crc="9036d879" region="mainbios" offset="0"/>
+        <device_ref name="ym2610"/>
+    </machine>
+    <machine name="mslug" sourcefile="neogeo/neogeo.cpp" romof="neogeo">
+        <description>Metal Slug - Super Vehicle-001</description>
+        <year>1996</year>
+        <manufacturer>Nazca</manufacturer>
+        <rom name="sp-s2.sp1" merge="sp-s2.sp1" bios="euro" size="131072" crc="9036d879" region="mainbios" offset="0"/>
+        <rom name="201-p1.p1" size="2097152" crc="08d8daa5" region="cslot1:maincpu" offset="100000"/>
+        <device_ref name="ym2610"/>
+        <dipswitch name="Free Play" tag="DSW" mask="64">
+            <diplocation name="SW" number="7"/>
+            <dipvalue name="Off" value="64" default="yes"/>
+            <dipvalue name="On" value="0"/>
+        </dipswitch>
+        <ramoption name="64K" default="yes">65536</ramoption>
+        <driver status="imperfect" emulation="good" savestate="supported"/>
+    </machine>
+    <machine name="ym2610" sourcefile="sound/2610intf.cpp" isdevice="yes" runnable="no">
+        <description>YM2610</description>
+        <rom name="ym2610.bin" size="16" crc="11111111" region="deltat" offset="0"/>
+    </machine>
+</mame>"#;
+
+    let document = listxml::parse_document(LISTXML).unwrap();
+    assert_eq!(
+        document.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+        ["mame", "machine"]
+    );
+    let mame = document.entry("mame").unwrap().next().unwrap();
+    assert_eq!(
+        mame.entry_unique("mameconfig")
+            .and_then(EntryData::as_scalar),
+        Some("10")
+    );
+
+    let machines: Vec<_> = document.entry("machine").unwrap().collect();
+    assert_eq!(machines.len(), 3);
+    let mslug = machines[1];
+    assert_eq!(
+        mslug.entry_unique("year").and_then(EntryData::as_scalar),
+        Some("1996")
+    );
+
+    let dipswitch = mslug
+        .entry_unique("dipswitch")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    let values: Vec<_> = dipswitch
+        .entry_iter("dipvalue")
+        .unwrap()
+        .filter_map(|value| value.as_sub_entry()?.value_unique("name"))
+        .collect();
+    assert_eq!(values, ["Off", "On"]);
+    let location = dipswitch
+        .entry_unique("diplocation")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    assert_eq!(location.value_unique("number"), Some("7"));
+
+    let ramoption = mslug
+        .entry_unique("ramoption")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    assert_eq!(ramoption.value_unique("name"), Some("64K"));
+    assert_eq!(ramoption.value_unique("text"), Some("65536"));
+
+    let graph = SetGraph::new(&document);
+    assert_eq!(graph.bios_of("mslug"), Some("neogeo"));
+    assert!(graph.issues().is_empty());
+
+    let split = document.rebuild(Layout::Split);
+    let mslug = split.entry("machine").unwrap().nth(1).unwrap();
+    let roms: Vec<_> = mslug
+        .entry_iter("rom")
+        .unwrap()
+        .filter_map(|rom| rom.as_sub_entry()?.value_unique("name"))
+        .collect();
+    assert_eq!(roms, ["201-p1.p1"]);
+
+    let err = listxml::parse_document("<datafile/>").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a <mame> element, found <datafile>"
+    );
+}
+
+#[test]
+fn parse_romcenter_document() {
+    use crate::romcenter;
+
+    const DAT: &str = "\u{feff}[CREDITS]\r
+author=Logiqx\r
+version=0.78\r
+[DAT]\r
+version=2.50\r
+plugin=arcade.dll\r
+[EMULATOR]\r
+refname=MAME\r
+version=MAME 0.78\r
+[GAMES]\r
+¬mslug¬Metal Slug¬mslug¬Metal Slug¬201-p1.bin¬08d8daa5¬2097152¬neogeo¬¬\r
+¬mslug¬Metal Slug¬mslugj¬Metal Slug (Japan)¬201-p1.bin¬08d8daa5¬2097152¬mslug¬201-p1.bin¬\r
+¬mslug¬Metal Slug¬mslug¬Metal Slug¬201-s1.bin¬2f55958d¬131072¬neogeo¬¬\r
+; unknown sections are ignored\r
+[DISKS]\r
+¬mslug¬¬\r
+[RESOURCES]\r
+¬neogeo¬Neo-Geo¬neogeo¬Neo-Geo¬¬¬¬¬¬\r
+";
+
+    let document = romcenter::parse_document(DAT).unwrap();
+    let header = document.entry("clrmamepro").unwrap().next().unwrap();
+    let scalar = |key| header.entry_unique(key).and_then(EntryData::as_scalar);
+    assert_eq!(scalar("name"), Some("MAME"));
+    assert_eq!(scalar("description"), Some("MAME 0.78"));
+    assert_eq!(scalar("author"), Some("Logiqx"));
+    assert_eq!(scalar("version"), Some("0.78"));
+    let romcenter = header
+        .entry_unique("romcenter")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    assert_eq!(romcenter.value_unique("plugin"), Some("arcade.dll"));
+
+    let games: Vec<_> = document.entry("game").unwrap().collect();
+    assert_eq!(games.len(), 2);
+    let mslug = games[0];
+    assert_eq!(
+        mslug.entry_unique("name").and_then(EntryData::as_scalar),
+        Some("mslug")
+    );
+    assert_eq!(
+        mslug.entry_unique("cloneof").and_then(EntryData::as_scalar),
+        None
+    );
+    assert_eq!(
+        mslug.entry_unique("romof").and_then(EntryData::as_scalar),
+        Some("neogeo")
+    );
+    let roms: Vec<_> = mslug
+        .entry("rom")
+        .unwrap()
+        .iter()
+        .filter_map(EntryData::as_sub_entry)
+        .collect();
+    assert_eq!(roms.len(), 2);
+    assert_eq!(roms[1].value_unique("crc"), Some("2f55958d"));
+    assert_eq!(roms[1].value_unique("merge"), None);
+
+    let mslugj = games[1];
+    assert_eq!(
+        mslugj
+            .entry_unique("cloneof")
+            .and_then(EntryData::as_scalar),
+        Some("mslug")
+    );
+    let rom = mslugj
+        .entry_unique("rom")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    assert_eq!(rom.value_unique("merge"), Some("201-p1.bin"));
+    assert_eq!(rom.value_unique("size"), Some("2097152"));
+
+    let neogeo = document.entry("resource").unwrap().next().unwrap();
+    assert_eq!(
+        neogeo
+            .entry_unique("description")
+            .and_then(EntryData::as_scalar),
+        Some("Neo-Geo")
+    );
+    assert!(neogeo.entry("rom").is_none());
+
+    let err = romcenter::parse_document("[GAMES]\n¬a¬b¬c¬d¬\n").unwrap_err();
+    assert_eq!(
+        err.to_string().lines().next(),
+        Some("expected `¬` at line 2, column 10 in game")
+    );
+    let err = romcenter::parse_document("[CREDITS]\nauthor\n").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("expected `=` at line 2, column 7"));
+    let err = romcenter::parse_document("[GAMES\n").unwrap_err();
+    assert!(err.to_string().starts_with("expected `]` at line 1"));
+    let err = romcenter::parse_document("[GAMES]\n¬a¬¬¬¬¬¬¬¬¬\n").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("expected a set name at line 2, column 5"));
+}
+
+#[test]
+fn write_romcenter_round_trip() {
+    use crate::romcenter;
+
+    const DAT: &str = r#"clrmamepro (
+        name MAME
+        description "MAME 0.78"
+        author Logiqx
+        romcenter ( version 2.50 plugin arcade.dll )
+    )
+
+    game (
+        name pacman
+        description Pac-Man
+        rom ( name pacman.6e size 4096 crc c1e6ab10 )
+    )
+
+    game (
+        name puckman
+        description PuckMan
+        cloneof pacman
+        romof pacman
+        rom ( name pacman.6e merge pacman.6e size 4096 crc c1e6ab10 )
+        rom ( name puckman.6f size 4096 crc 1a6fb2d4 )
+    )
+
+    resource ( name neogeo description Neo-Geo )"#;
+
+    let document = parse::parse_document(DAT).unwrap();
+    let dat = romcenter::to_string(&document).unwrap();
+    assert_eq!(
+        dat,
+        "[CREDITS]
+author=Logiqx
+[DAT]
+version=2.50
+plugin=arcade.dll
+[EMULATOR]
+refname=MAME
+version=MAME 0.78
+[GAMES]
+¬pacman¬Pac-Man¬pacman¬Pac-Man¬pacman.6e¬c1e6ab10¬4096¬¬¬
+¬pacman¬Pac-Man¬puckman¬PuckMan¬pacman.6e¬c1e6ab10¬4096¬pacman¬pacman.6e¬
+¬pacman¬Pac-Man¬puckman¬PuckMan¬puckman.6f¬1a6fb2d4¬4096¬pacman¬¬
+[RESOURCES]
+¬neogeo¬Neo-Geo¬neogeo¬Neo-Geo¬¬¬¬¬¬
+"
+    );
+
+    let reparsed = romcenter::parse_document(&dat).unwrap();
+    assert_eq!(reparsed, document);
+    assert_eq!(romcenter::to_string(&reparsed).unwrap(), dat);
+
+    for dat in [
+        "game ( name a¬b )",
+        "game ( name a rom ( name \"b\nc\" ) )",
+        "clrmamepro ( author \"a\r\nb\" )",
+        "game ( name a disk ( name b ) )",
+        "game ( name a sample b )",
+        "game ( name a year 1980 )",
+        "game ( name a description b description c )",
+        "game ( description a )",
+        "game ( name a rom ( name b sha1 00 ) )",
+        "game ( name a rom ( size 1 ) )",
+        "game ( name a rom b )",
+        "clrmamepro ( name a ) clrmamepro ( name b )",
+        "clrmamepro ( emulator ( name a ) )",
+        "clrmamepro ( romcenter ( a ( b c ) ) )",
+        "emulator ( name a )",
+    ]
+    .iter()
+    {
+        let document = parse::parse_document(dat).unwrap();
+        assert!(romcenter::to_string(&document).is_err(), "{}", dat);
+    }
+
+    const MAME: &str = r#"clrmamepro ( name MAME description "MAME 0.78" romcenter ( version 2.50 ) )
+    game (
+        name pacman
+        description Pac-Man
+        year 1980
+        rom ( name pacman.6e size 4096 crc c1e6ab10 sha1 e87e059c5be45753f7e9f33dff851f16d6751181 )
+        sample siren
+    )"#;
+
+    let mut document = parse::parse_document(MAME).unwrap();
+    assert!(romcenter::to_string(&document).is_err());
+    for game in document.entry_mut("game").unwrap() {
+        game.retain(|key, _| key == "name" || key == "description" || key == "rom");
+        for rom in game.entry_mut("rom").unwrap() {
+            if let EntryData::SubEntry(rom) = rom {
+                rom.retain(|key, _| key != "sha1");
+            }
+        }
+    }
+    let dat = romcenter::to_string(&document).unwrap();
+    assert_eq!(romcenter::parse_document(&dat).unwrap(), document);
+}
+
+#[test]
+fn graph_rebuild_split_sets() {
+    use crate::graph::Layout;
+
+    const DAT: &str = r#"game (
+        name bios
+        rom ( name b.bin size 1 crc 00000001 )
+    )
+    game (
+        name parent
+        romof bios
+        rom ( name b.bin merge b.bin size 1 crc 00000001 )
+        rom ( name p.bin size 2 crc 00000002 )
+        rom ( name q.bin size 3 crc 00000003 )
+    )
+    game (
+        name clone
+        cloneof parent
+        romof parent
+        rom ( name b.bin merge b.bin size 1 crc 00000001 )
+        rom ( name p.bin size 2 crc 0000000f )
+        rom ( name q.bin merge q.bin size 3 crc 00000003 )
+        rom ( name c.bin size 4 crc 00000004 )
+    )"#;
+
+    fn roms<'a>(document: &'a DatDocument<'a>, name: &str) -> Vec<(&'a str, Option<&'a str>)> {
+        document
+            .entry("game")
+            .unwrap()
+            .find(|set| set.entry_unique("name").and_then(EntryData::as_scalar) == Some(name))
+            .and_then(|set| set.entry_iter("rom"))
+            .map(|roms| {
+                roms.filter_map(EntryData::as_sub_entry)
+                    .map(|rom| (rom.value_unique("name").unwrap(), rom.value_unique("crc")))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    let document = parse::parse_document(DAT).unwrap();
+    let split = document.rebuild(Layout::Split);
+    assert_eq!(
+        roms(&split, "parent"),
+        [("p.bin", Some("00000002")), ("q.bin", Some("00000003"))]
+    );
+    assert_eq!(
+        roms(&split, "clone"),
+        [("p.bin", Some("0000000f")), ("c.bin", Some("00000004"))]
+    );
+
+    let non_merged = split.rebuild(Layout::NonMerged);
+    assert_eq!(
+        roms(&non_merged, "parent"),
+        [
+            ("p.bin", Some("00000002")),
+            ("q.bin", Some("00000003")),
+            ("b.bin", Some("00000001"))
+        ]
+    );
+    assert_eq!(
+        roms(&non_merged, "clone"),
+        [
+            ("p.bin", Some("0000000f")),
+            ("c.bin", Some("00000004")),
+            ("q.bin", Some("00000003")),
+            ("b.bin", Some("00000001"))
+        ]
+    );
+    assert_eq!(non_merged.rebuild(Layout::Split), split);
+
+    let merged = document.rebuild(Layout::Merged);
+    assert_eq!(
+        roms(&merged, "parent"),
+        [
+            ("p.bin", Some("00000002")),
+            ("q.bin", Some("00000003")),
+            ("clone/p.bin", Some("0000000f")),
+            ("c.bin", Some("00000004"))
+        ]
+    );
+}
+
+#[test]
+fn write_skips_empty_sub_entries() {
+    let game = EntryFragment::new()
+        .with("name", "Empty")
+        .with("rom", SubEntry::new())
+        .with("driver", SubEntry::new().with("emulation", SubEntry::new()))
+        .with(
+            "disk",
+            SubEntry::new()
+                .with("name", "disk")
+                .with("extra", SubEntry::new()),
+        );
+    let doc = DatDocument::new().with("game", game);
+
+    let dat = doc.to_string();
+    assert_eq!(dat, "game (\n\tname Empty\n\tdisk ( name disk )\n)\n");
+    assert!(parse::parse_document(&dat).is_ok());
+
+    let mut output = String::new();
+    SubEntry::new()
+        .write_to("rom", &mut output, &WriteOptions::default())
+        .unwrap();
+    assert_eq!(output, "");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader_unclosed_quote() {
+    use std::io::{BufReader, Cursor};
+
+    for dat in [
+        "game ( name \" )\ngame ( name y )\ngame ( name z )",
+        "game ( name \" )\ngame ( name y )\ngame ( name \" z 1 )",
+        "game ( name a\" )\ngame ( name \"b\\\" )\" )",
+    ]
+    .iter()
+    {
+        assert!(parse::parse_document(dat).is_ok());
+        let expected: Vec<_> = parse::fragments(dat).map(Result::unwrap).collect();
+        for capacity in [1, 8192].iter() {
+            let reader = parse::Reader::new(BufReader::with_capacity(*capacity, Cursor::new(dat)));
+            let fragments: Vec<_> = reader.map(Result::unwrap).collect();
+            assert_eq!(fragments.len(), expected.len(), "{}", dat);
+            for ((key, fragment), (expected_key, expected_fragment)) in
+                fragments.iter().zip(expected.iter())
+            {
+                assert_eq!(key, expected_key);
+                assert_eq!(
+                    format!("{:?}", fragment),
+                    format!("{:?}", expected_fragment)
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader_large_fragment() {
+    use std::io::Cursor;
+
+    let mut dat = String::from("game (\n\tname \"a ) b \\\" ) c\"\n");
+    for i in 0..2000 {
+        dat.push_str(&format!("\trom ( name \"{0}).bin\" size {0} )\n", i));
+    }
+    dat.push_str(")\ngame ( name last )\n");
+
+    let expected: Vec<_> = parse::fragments(&dat).map(Result::unwrap).collect();
+    let fragments: Vec<_> = parse::Reader::new(Cursor::new(&dat))
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(fragments.len(), 2);
+    for ((key, fragment), (expected_key, expected_fragment)) in
+        fragments.iter().zip(expected.iter())
+    {
+        assert_eq!(key, expected_key);
+        assert_eq!(
+            format!("{:?}", fragment),
+            format!("{:?}", expected_fragment)
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader_reads_one_fragment() {
+    use std::io::{self, BufReader, Read};
+
+    /// Counts the bytes read from the input.
+    struct Counting<'a> {
+        input: &'a [u8],
+        read: usize,
+    }
+
+    impl<'a> Read for Counting<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.input.read(buf)?;
+            self.read += read;
+            Ok(read)
+        }
+    }
+
+    const FIRST: &str = "game ( name a description (proto) rom ( name (a).bin ) )\n";
+    let mut dat = String::from(FIRST);
+    for _ in 0..1000 {
+        dat.push_str("game ( name b )\n");
+    }
+
+    let counting = Counting {
+        input: dat.as_bytes(),
+        read: 0,
+    };
+    let mut reader = parse::Reader::new(BufReader::with_capacity(16, counting));
+    let (_, fragment) = reader.next().unwrap().unwrap();
+    assert_eq!(
+        fragment
+            .entry_unique("description")
+            .and_then(EntryData::as_scalar),
+        Some("(proto)")
+    );
+    assert!(reader.into_inner().get_ref().read <= FIRST.len() + 16);
+}
+
+#[test]
+#[allow(deprecated)]
+fn sub_entry_value() {
+    let (_, fragment) = parse::parse_fragment("game ( rom ( name a.bin name b.bin ) )").unwrap();
+    let rom = fragment
+        .entry_unique("rom")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    let names: Vec<_> = rom.value("name").unwrap().iter().collect();
+    assert_eq!(
+        names,
+        [
+            &EntryData::Scalar("a.bin".into()),
+            &EntryData::Scalar("b.bin".into())
+        ]
+    );
+    assert!(rom.value("size").is_none());
+}
+
+#[test]
+fn parse_deep_nesting() {
+    use crate::cst;
+
+    let nested = |depth: usize| {
+        let mut dat = String::from("game ( name x ");
+        for _ in 0..depth {
+            dat.push_str("a ( ");
+        }
+        dat.push_str("b 1");
+        for _ in 0..depth {
+            dat.push_str(" )");
+        }
+        dat.push_str(" )");
+        dat
+    };
+
+    let dat = nested(parse::MAX_DEPTH);
+    assert!(parse::parse_document(&dat).is_ok());
+    assert_eq!(cst::parse_document(&dat).unwrap().to_string(), dat);
+
+    let dat = nested(parse::MAX_DEPTH + 1);
+    let err = parse::parse_document(&dat).unwrap_err();
+    assert!(err.to_string().starts_with(
+        "expected at most 64 nested sub-entries at line 1, column 273 in game (name \"x\")"
+    ));
+    assert_eq!(
+        cst::parse_document(&dat).unwrap_err().to_string(),
+        err.to_string()
+    );
+}
+
+#[test]
+fn write_round_trip_parentheses() {
+    const DAT: &str =
+        "game (\n\tname \"(\"\n\tdescription \"(x\"\n\trom ( name \"a)\" size 1 )\n)\n";
+
+    let document = parse::parse_document(DAT).unwrap();
+    let dat = document.to_string();
+    assert_eq!(
+        dat,
+        "game (\n\tname \"(\"\n\tdescription \"(x\"\n\trom ( name \"a)\" size 1 )\n)\n"
+    );
+    assert_eq!(parse::parse_document(&dat).unwrap().to_string(), dat);
+}
+
+#[test]
+fn write_invalid_keys() {
+    let (_, fragment) = parse::parse_fragment("game ( name a )").unwrap();
+    let options = WriteOptions::default();
+    assert!(fragment
+        .write_to("game", &mut String::new(), &options)
+        .is_ok());
+    for key in ["", "two words", "quo\"te", "(", "a)"].iter() {
+        assert!(fragment
+            .write_to(key, &mut String::new(), &options)
+            .is_err());
+
+        let (_, mut invalid) = parse::parse_fragment("game ( name a )").unwrap();
+        invalid
+            .keys
+            .insert((*key).into(), Node::Unique(EntryData::Scalar("x".into())));
+        assert!(invalid
+            .write_to("game", &mut String::new(), &options)
+            .is_err());
+    }
+}
+
+#[test]
+fn cst_round_trip() {
+    use crate::cst;
+
+    const DAT: &str = "\u{feff}clrmamepro (\r\n\tname \"Cave Story\"\r\n\tcomment \"keep  this\"\r\n)\r\n\r\ngame(\r\n    name   \"Cave Story (En)\"\r\n    rom ( name Doukutsu.exe size 1478656 crc c5a2a3f6 )\r\n    driver (\r\n        status good\r\n    )\r\n)\r\n   game ( name \"a \\\"b\\\"\" )  \r\n";
+
+    let document = cst::parse_document(DAT).unwrap();
+    assert_eq!(document.to_string(), DAT);
+    assert_eq!(document.to_document(), parse::parse_document(DAT).unwrap());
+    assert_eq!(document.fragments().count(), 3);
+    assert_eq!(document.fragments().nth(2).unwrap().name(), Some("a \"b\""));
+
+    for input in [
+        "",
+        "game ( name a",
+        "game ( name a ) game ( rom ( ) )",
+        "game ( name a ) game",
+        "game ( rom ( name a size ) )",
+        "game ( name a ) )",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            cst::parse_document(input).unwrap_err().to_string(),
+            parse::parse_document(input).unwrap_err().to_string()
+        );
+    }
+}
+
+#[test]
+fn cst_edit_document() {
+    use crate::cst;
+
+    const DAT: &str = r#"clrmamepro (
+	name "Test"
+)
+
+game (
+	name "Tetris (Japan)"
+	rom ( name "Tetris (Japan).gb" size 32768 crc 6ab7b1fb )
+	rom ( name extra.gb size 0 )
+)
+
+game (
+	name Other
+)
+"#;
+
+    let mut document = cst::parse_document(DAT).unwrap();
+    let game = document.find_mut("game", "Tetris (Japan)").unwrap();
+    let rom = game
+        .entries_mut()
+        .filter_map(cst::Entry::as_sub_entry_mut)
+        .find(|rom| rom.value("name") == Some("extra.gb"))
+        .unwrap();
+    rom.set("size", "16");
+    rom.set("crc", "deadbeef");
+    game.entry_mut("name").unwrap().set_value("Tetris (World)");
+    game.push("year", "1989");
+    game.push(
+        "disk",
+        SubEntry::new()
+            .with("name", "disk one")
+            .with("sha1", "00")
+            .with("flags", SubEntry::new().with("none", SubEntry::new())),
+    );
+    game.push("sample", SubEntry::new());
+    assert_eq!(
+        document.to_string(),
+        r#"clrmamepro (
+	name "Test"
+)
+
+game (
+	name "Tetris (World)"
+	rom ( name "Tetris (Japan).gb" size 32768 crc 6ab7b1fb )
+	rom ( name extra.gb size 16 crc deadbeef )
+	year 1989
+	disk ( name "disk one" sha1 00 )
+)
+
+game (
+	name Other
+)
+"#
+    );
+
+    let game = document.find_mut("game", "Tetris (World)").unwrap();
+    assert_eq!(game.remove("rom").len(), 2);
+    game.retain(|entry| entry.key() != "disk");
+    game.entry_mut("year").unwrap().rename("releaseyear");
+    document.remove(0);
+    document.push(cst::Fragment::new("game").with("name", "New Game"));
+    assert_eq!(
+        document.to_string(),
+        r#"game (
+	name "Tetris (World)"
+	releaseyear 1989
+)
+
+game (
+	name Other
+)
+
+game (
+	name "New Game"
+)
+"#
+    );
+    assert!(document.remove(3).is_none());
+    assert_eq!(
+        parse::parse_document(&document.to_string()).unwrap(),
+        document.to_document()
+    );
+}
+
+#[test]
+fn cst_deep_nesting() {
+    use crate::cst;
+
+    let nested = |depth: usize| {
+        let mut dat = String::from("game ( name x ");
+        for _ in 0..depth {
+            dat.push_str("a ( ");
+        }
+        dat.push_str("b 1");
+        for _ in 0..depth {
+            dat.push_str(" )");
+        }
+        dat.push_str(" )");
+        dat
+    };
+
+    let dat = nested(parse::MAX_DEPTH);
+    assert_eq!(cst::parse_document(&dat).unwrap().to_string(), dat);
+
+    let err = cst::parse_document(&nested(parse::MAX_DEPTH + 1)).unwrap_err();
+    assert!(err.to_string().starts_with(
+        "expected at most 64 nested sub-entries at line 1, column 273 in game (name \"x\")"
+    ));
+}
+
+#[cfg(feature = "logiqx")]
+#[test]
+fn parse_logiqx_deep_nesting() {
+    use crate::logiqx;
+
+    let nested = |depth: usize| {
+        let mut dat = String::from("<datafile><game name=\"x\">");
+        for _ in 0..depth {
+            dat.push_str("<a b=\"1\">");
+        }
+        for _ in 0..depth {
+            dat.push_str("</a>");
+        }
+        dat.push_str("</game></datafile>");
+        dat
+    };
+
+    assert!(logiqx::parse_document(&nested(parse::MAX_DEPTH)).is_ok());
+    match logiqx::parse_document(&nested(parse::MAX_DEPTH + 1)) {
+        Err(Error::XmlError(err)) => {
+            assert!(err.starts_with("sub-entries nested more than 64 deep"))
+        }
+        _ => panic!("expected an XML error"),
+    }
+}
+
+#[test]
+fn span_elements() {
+    use crate::span::{self, LineIndex};
+
+    const DAT: &str = "clrmamepro ( name Tést )\r\ngame (\r\n\tname \"a \\\"b\\\"\"\r\n\tdescription \"Ünïcode\"\r\n\trom ( name x.bin size 1 )\r\n)\r\n";
+
+    let document = parse::parse_document(DAT).unwrap();
+    let lines = LineIndex::new(DAT);
+    assert_eq!(lines.line_count(), 7);
+    assert_eq!(lines.line(2), Some("game ("));
+    assert_eq!(lines.line(7), Some(""));
+    assert_eq!(lines.line(0), None);
+    assert_eq!(lines.line(8), None);
+
+    let header = document.entry("clrmamepro").unwrap().next().unwrap();
+    assert_eq!(header.span(DAT), Some("( name Tést )"));
+    assert_eq!(
+        lines.line_column(header.byte_range(DAT).unwrap().end),
+        Some((1, 25))
+    );
+    assert_eq!(lines.line_column(DAT.len()), Some((7, 1)));
+    assert_eq!(lines.line_column(20), None);
+    assert_eq!(lines.line_column(DAT.len() + 1), None);
+
+    let game = document.entry("game").unwrap().next().unwrap();
+    assert_eq!(game.entry_unique("name").unwrap().byte_range(DAT), None);
+    let description = game.entry_unique("description").unwrap();
+    assert_eq!(description.span(DAT), Some("\"Ünïcode\""));
+    let range = game.key_range("description", DAT).unwrap();
+    assert_eq!(lines.line_column(range.start), Some((4, 2)));
+    assert_eq!(&DAT[range], "description");
+    assert_eq!(game.key_range("year", DAT), None);
+    assert_eq!(
+        game.span(DAT),
+        Some("(\r\n\tname \"a \\\"b\\\"\"\r\n\tdescription \"Ünïcode\"\r\n\trom ( name x.bin size 1 )\r\n)")
+    );
+
+    let rom = game
+        .entry_unique("rom")
+        .and_then(EntryData::as_sub_entry)
+        .unwrap();
+    let size = rom.entry_unique("size").unwrap();
+    assert_eq!(
+        lines.line_column(size.byte_range(DAT).unwrap().start),
+        Some((5, 24))
+    );
+
+    assert_eq!(span::byte_range(DAT, "game"), None);
+    let mut edited = game.clone();
+    edited.insert("year", "1980");
+    assert_eq!(edited.byte_range(DAT), game.byte_range(DAT));
+
+    const QUOTED: &str = "game ( name \"Cave Story (En)\" rom ( size 1 name \"Doukutsu.exe\" ) )";
+    let document = parse::parse_document(QUOTED).unwrap();
+    let game = document.entry("game").unwrap().next().unwrap();
+    assert_eq!(
+        game.span(QUOTED),
+        Some("( name \"Cave Story (En)\" rom ( size 1 name \"Doukutsu.exe\" ) )")
+    );
+    let rom = game.entry_unique("rom").unwrap();
+    assert_eq!(rom.span(QUOTED), Some("( size 1 name \"Doukutsu.exe\" )"));
+    let name = game.entry_unique("name").unwrap();
+    assert_eq!(name.span(QUOTED), Some("\"Cave Story (En)\""));
+}
@@ -0,0 +1,282 @@
+//! Writing routines for ListInfo DAT documents.
+//!
+//! The parsed expression tree can be written back out as ListInfo DAT text
+//! with `DatDocument::write_to`, `EntryFragment::write_to` and `SubEntry::write_to`.
+//! Keys are written in the order they appear in the tree, and values are quoted
+//! only when they are empty or contain whitespace, quotes or parentheses. Quotes and
+//! backslashes within quoted values are escaped as `\"` and `\\`. Sub-entries without any
+//! values are skipped, since the grammar requires every sub-entry to contain at least one
+//! entry. Keys can not be quoted, so writing fails with `fmt::Error` if a key is empty or
+//! contains whitespace, quotes or parentheses, rather than writing text that does not parse.
+//!
+//! ```rust
+//! use listinfo::parse;
+//! use listinfo::write::{Indent, LineEnding, WriteOptions};
+//!
+//! const GAME: &str = r#"game (
+//!         name "Cave Story (En)"
+//!         rom ( name Doukutsu.exe size 1478656 )
+//!     )"#;
+//!
+//! let document = parse::parse_document(GAME).unwrap();
+//!
+//! let mut dat = String::new();
+//! document.write_to(&mut dat, &WriteOptions::default()).unwrap();
+//! assert_eq!(dat, "game (\n\tname \"Cave Story (En)\"\n\trom ( name Doukutsu.exe size 1478656 )\n)\n");
+//!
+//! let options = WriteOptions {
+//!     indent: Indent::Spaces(2),
+//!     line_ending: LineEnding::CrLf,
+//!     inline_sub_entries: false,
+//! };
+//! let mut dat = String::new();
+//! document.write_to(&mut dat, &options).unwrap();
+//! assert_eq!(dat, "game (\r\n  name \"Cave Story (En)\"\r\n  rom (\r\n    name Doukutsu.exe\r\n    size 1478656\r\n  )\r\n)\r\n");
+//! ```
+
+use alloc::borrow::Cow;
+use core::fmt::{self, Display, Formatter, Write};
+
+use indexmap::IndexMap;
+
+use crate::elements::*;
+
+/// The indentation used for the items of fragments and sub-entries.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Indent {
+    /// Indent with a single tab per level.
+    Tab,
+    /// Indent with the given number of spaces per level.
+    Spaces(usize),
+}
+
+/// The line ending written after every line.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineEnding {
+    /// Unix style `\n` line endings.
+    Lf,
+    /// Windows style `\r\n` line endings.
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Options that control the layout of written ListInfo DAT text.
+///
+/// The default options follow the layout clrmamepro emits: tab indentation,
+/// `\n` line endings and single-line sub-entries such as `rom ( name "x" size 1 )`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WriteOptions {
+    /// The indentation used for items.
+    pub indent: Indent,
+    /// The line ending written after every line.
+    pub line_ending: LineEnding,
+    /// Whether sub-entries are written on a single line.
+    pub inline_sub_entries: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: Indent::Tab,
+            line_ending: LineEnding::Lf,
+            inline_sub_entries: true,
+        }
+    }
+}
+
+impl WriteOptions {
+    pub(crate) fn write_indent<W: Write>(&self, w: &mut W, depth: usize) -> fmt::Result {
+        for _ in 0..depth {
+            match self.indent {
+                Indent::Tab => w.write_char('\t')?,
+                Indent::Spaces(n) => {
+                    for _ in 0..n {
+                        w.write_char(' ')?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_newline<W: Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_str(self.line_ending.as_str())
+    }
+}
+
+/// Writes a key, failing if it could not be read back as the same key.
+fn write_key<W: Write>(w: &mut W, key: &str) -> fmt::Result {
+    if key.is_empty()
+        || key.contains(|c: char| c.is_whitespace() || c == '"' || c == '(' || c == ')')
+    {
+        return Err(fmt::Error);
+    }
+    w.write_str(key)
+}
+
+/// Displays a value with quotes and backslashes escaped, for writing within quotes.
+pub(crate) struct Escaped<'a>(pub(crate) &'a str);
+
+impl<'a> Display for Escaped<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in self.0.char_indices() {
+            if c == '"' || c == '\\' {
+                f.write_str(&self.0[start..i])?;
+                f.write_char('\\')?;
+                start = i;
+            }
+        }
+        f.write_str(&self.0[start..])
+    }
+}
+
+/// Whether a value must be quoted to be read back as a single value.
+pub(crate) fn needs_quotes(value: &str) -> bool {
+    value.is_empty()
+        || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '(' || c == ')')
+}
+
+fn write_value<W: Write>(w: &mut W, value: &str) -> fmt::Result {
+    if needs_quotes(value) {
+        write!(w, "\"{}\"", Escaped(value))
+    } else {
+        w.write_str(value)
+    }
+}
+
+impl<'a> DatDocument<'a> {
+    /// Writes the document as ListInfo DAT text.
+    ///
+    /// Fragments are written grouped by key, in the order each key first appeared.
+    pub fn write_to<W: Write>(&self, w: &mut W, options: &WriteOptions) -> fmt::Result {
+        for (i, (key, fragment)) in self
+            .document
+            .iter()
+            .flat_map(|(key, fragments)| fragments.iter().map(move |f| (key, f)))
+            .enumerate()
+        {
+            if i > 0 {
+                options.write_newline(w)?;
+            }
+            fragment.write_to(key, w, options)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for DatDocument<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_to(f, &WriteOptions::default())
+    }
+}
+
+/// Whether the entry is a sub-entry that contains no values, even through nested sub-entries.
+///
+/// Such a sub-entry would be written as `key ( )`, which does not parse.
+pub(crate) fn writes_nothing(data: &EntryData<'_>) -> bool {
+    match data {
+        EntryData::Scalar(_) => false,
+        EntryData::SubEntry(sub_entry) => is_blank(sub_entry),
+    }
+}
+
+fn is_blank(sub_entry: &SubEntry<'_>) -> bool {
+    sub_entry
+        .keys
+        .values()
+        .flat_map(Node::iter)
+        .all(writes_nothing)
+}
+
+/// Writes each entry on its own line at the given depth.
+fn write_entries<W: Write>(
+    keys: &IndexMap<Cow<'_, str>, Node<EntryData<'_>>>,
+    w: &mut W,
+    options: &WriteOptions,
+    depth: usize,
+) -> fmt::Result {
+    for (key, node) in keys.iter() {
+        for data in node.iter().filter(|data| !writes_nothing(data)) {
+            options.write_indent(w, depth)?;
+            match data {
+                EntryData::Scalar(value) => {
+                    write_key(w, key)?;
+                    w.write_char(' ')?;
+                    write_value(w, value)?;
+                }
+                EntryData::SubEntry(sub_entry) => sub_entry.write_at(key, w, options, depth)?,
+            }
+            options.write_newline(w)?;
+        }
+    }
+    Ok(())
+}
+
+impl<'a> EntryFragment<'a> {
+    /// Writes the fragment with the given key as ListInfo DAT text.
+    pub fn write_to<W: Write>(&self, key: &str, w: &mut W, options: &WriteOptions) -> fmt::Result {
+        write_key(w, key)?;
+        w.write_str(" (")?;
+        options.write_newline(w)?;
+        write_entries(&self.keys, w, options, 1)?;
+        w.write_char(')')?;
+        options.write_newline(w)
+    }
+}
+
+impl<'a> SubEntry<'a> {
+    /// Writes the sub-entry with the given key as ListInfo DAT text.
+    ///
+    /// Nested sub-entries are written inline or indented along with their parent.
+    /// Nothing is written for a sub-entry without any values.
+    pub fn write_to<W: Write>(&self, key: &str, w: &mut W, options: &WriteOptions) -> fmt::Result {
+        if is_blank(self) {
+            return Ok(());
+        }
+        self.write_at(key, w, options, 0)?;
+        options.write_newline(w)
+    }
+
+    fn write_at<W: Write>(
+        &self,
+        key: &str,
+        w: &mut W,
+        options: &WriteOptions,
+        depth: usize,
+    ) -> fmt::Result {
+        write_key(w, key)?;
+        w.write_str(" (")?;
+        if options.inline_sub_entries {
+            for (key, node) in self.keys.iter() {
+                for data in node.iter().filter(|data| !writes_nothing(data)) {
+                    w.write_char(' ')?;
+                    match data {
+                        EntryData::Scalar(value) => {
+                            write_key(w, key)?;
+                            w.write_char(' ')?;
+                            write_value(w, value)?;
+                        }
+                        EntryData::SubEntry(sub_entry) => {
+                            sub_entry.write_at(key, w, options, depth)?
+                        }
+                    }
+                }
+            }
+            w.write_str(" )")
+        } else {
+            options.write_newline(w)?;
+            write_entries(&self.keys, w, options, depth + 1)?;
+            options.write_indent(w, depth)?;
+            w.write_char(')')
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Reading XML DAT files into the element model.
+//!
+//! The root element of a DAT is returned separately, and every child of the root is read as a
+//! fragment with the same key, except for `<header>`, which is read as a `clrmamepro` fragment
+//! with the attributes of its `<clrmamepro>` element read as values of the header.
+//!
+//! Within a fragment, attributes are read as values, and child elements are read as values if
+//! they only contain text, or as sub-entries otherwise. The text of an element that also has
+//! attributes or children is read as its `text` value. Text is only copied if it contained an
+//! escape sequence.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str;
+
+use quick_xml::events::attributes::Attributes;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::elements::*;
+use crate::error::Error;
+use crate::parse::MAX_DEPTH;
+
+/// An element that is being read, which becomes a value or sub-entry when it ends.
+pub(crate) struct Element<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) entries: SubEntry<'a>,
+    text: Option<Cow<'a, str>>,
+}
+
+/// Reads an XML DAT, returning its root element and the fragments within it.
+pub(crate) fn read_document(input: &str) -> Result<(Element<'_>, DatDocument<'_>), Error> {
+    let mut reader = Reader::from_str(input);
+    reader.trim_text(true);
+
+    let mut document = DatDocument::new();
+    let mut root = None;
+    let mut stack: Vec<Element<'_>> = Vec::new();
+    loop {
+        let event = reader.read_event().map_err(|err| xml_error(&reader, err))?;
+        match event {
+            Event::Start(start) => {
+                check_depth(&reader, &stack)?;
+                let element = element(input, &start).map_err(|err| xml_error(&reader, err))?;
+                if root.is_none() {
+                    root = Some(element);
+                } else {
+                    stack.push(element);
+                }
+            }
+            Event::Empty(start) => {
+                check_depth(&reader, &stack)?;
+                let element = element(input, &start).map_err(|err| xml_error(&reader, err))?;
+                if root.is_none() {
+                    root = Some(element);
+                    break;
+                }
+                end_element(&mut document, &mut stack, element);
+            }
+            Event::End(_) => match stack.pop() {
+                Some(element) => end_element(&mut document, &mut stack, element),
+                None => break,
+            },
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|err| xml_error(&reader, err))?;
+                push_text(&mut stack, text);
+            }
+            Event::CData(data) => {
+                let data = match data.into_inner() {
+                    Cow::Borrowed(data) => str::from_utf8(data).map(Cow::Borrowed),
+                    Cow::Owned(data) => String::from_utf8(data)
+                        .map(Cow::Owned)
+                        .map_err(|err| err.utf8_error()),
+                };
+                let data = data.map_err(|err| xml_error(&reader, err))?;
+                push_text(&mut stack, data);
+            }
+            Event::Eof => {
+                return Err(xml_error(&reader, "unexpected end of input"));
+            }
+            Event::Decl(_) | Event::PI(_) | Event::DocType(_) | Event::Comment(_) => {}
+        }
+    }
+
+    match root {
+        Some(root) => Ok((root, document)),
+        None => Err(Error::XmlError("expected a root element".into())),
+    }
+}
+
+/// Reads the name and attributes of an element.
+///
+/// The element is read from the input it points into, so that its name and attributes
+/// borrow from the input rather than the event.
+fn element<'a>(input: &'a str, start: &BytesStart<'_>) -> Result<Element<'a>, String> {
+    let content = reborrow(input, start).ok_or("element does not borrow from the input")?;
+    let name_len = start.name().as_ref().len();
+    let name = content.get(..name_len).ok_or("invalid element name")?;
+
+    let mut entries = SubEntry::new();
+    for attribute in Attributes::new(content, name_len) {
+        let attribute = attribute.map_err(|err| err.to_string())?;
+        let key = str::from_utf8(attribute.key.into_inner()).map_err(|err| err.to_string())?;
+        let value = attribute.unescape_value().map_err(|err| err.to_string())?;
+        entries.push(key, value);
+    }
+    Ok(Element {
+        name,
+        entries,
+        text: None,
+    })
+}
+
+/// Gets the slice of the input that the bytes of a borrowed event point into.
+fn reborrow<'a>(input: &'a str, bytes: &[u8]) -> Option<&'a str> {
+    let start = (bytes.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    input.get(start..start.checked_add(bytes.len())?)
+}
+
+fn push_text<'a>(stack: &mut [Element<'a>], text: Cow<'a, str>) {
+    if let Some(element) = stack.last_mut() {
+        element.text = match element.text.take() {
+            Some(existing) => Some(Cow::Owned(existing.into_owned() + &text)),
+            None => Some(text),
+        };
+    }
+}
+
+/// Adds an element that has ended to its parent, or to the document if it is a fragment.
+fn end_element<'a>(
+    document: &mut DatDocument<'a>,
+    stack: &mut [Element<'a>],
+    element: Element<'a>,
+) {
+    let Element {
+        name,
+        mut entries,
+        text,
+    } = element;
+    let in_header = matches!(stack, [header] if header.name == "header");
+    let parent = match stack.last_mut() {
+        Some(parent) => parent,
+        None => {
+            if let Some(text) = text {
+                entries.push("text", text);
+            }
+            let key = if name == "header" { "clrmamepro" } else { name };
+            document.push(key, EntryFragment { keys: entries.keys });
+            return;
+        }
+    };
+
+    if in_header && name == "clrmamepro" {
+        for (key, node) in entries.keys {
+            for value in node.into_vec() {
+                parent.entries.push(key.clone(), value);
+            }
+        }
+    } else if entries.keys.is_empty() {
+        parent.entries.push(name, text.unwrap_or(Cow::Borrowed("")));
+    } else {
+        if let Some(text) = text {
+            entries.push("text", text);
+        }
+        parent.entries.push(name, entries);
+    }
+}
+
+/// Fails if an element within the open elements would be a sub-entry nested deeper than
+/// sub-entries can be parsed from a ListInfo DAT.
+fn check_depth(reader: &Reader<&[u8]>, stack: &[Element<'_>]) -> Result<(), Error> {
+    if stack.len() > MAX_DEPTH {
+        Err(xml_error(
+            reader,
+            format!("sub-entries nested more than {} deep", MAX_DEPTH),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn xml_error(reader: &Reader<&[u8]>, err: impl ToString) -> Error {
+    Error::XmlError(format!(
+        "{} at byte {}",
+        err.to_string(),
+        reader.buffer_position()
+    ))
+}